@@ -0,0 +1,17 @@
+use std::{env, process};
+use mylib::wordgame;
+
+// `wordgame pig-latin|ubbi-dubbi|double-dutch|rot13 [FILE] [--simple] [--suffix way|yay|hay]`
+// plays a word game on a file (or stdin) and writes the result to stdout,
+// a chunk at a time
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = wordgame::Options::new(&args).unwrap_or_else(|err| {
+        eprintln!("Problem parsing arguments: {}", err);
+        process::exit(1);
+    });
+    if let Err(e) = wordgame::run(&options) {
+        eprintln!("Application error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+// Why the directory couldn't make a change or find what was asked for
+#[derive(Debug, Clone, PartialEq)]
+pub enum DirectoryError {
+    NoDepartment(String),
+    NoEmployee { name: String, department: String },
+    // Two employees in one department can't have the same name
+    AlreadyIn { name: String, department: String },
+    // Names of employees and departments can't be blank
+    EmptyName,
+}
+
+impl fmt::Display for DirectoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DirectoryError::NoDepartment(department) => write!(f, "No department named \"{}\" found.", department),
+            DirectoryError::NoEmployee { name, department } => {
+                write!(f, "No employee named \"{}\" in department \"{}\".", name, department)
+            },
+            DirectoryError::AlreadyIn { name, department } => {
+                write!(f, "Employee with name \"{}\" already exists in department \"{}\".", name, department)
+            },
+            DirectoryError::EmptyName => write!(f, "Names can't be blank."),
+        }
+    }
+}
+
+impl Error for DirectoryError {}
+
+#[derive(Debug, Default)]
+pub struct Directory {
+    // Every department's employees, sorted. A department without employees
+    // isn't kept.
+    employees_by_department: HashMap<String, Vec<String>>,
+}
+
+impl Directory {
+    // Make new, empty directoy
+    pub fn new() -> Directory {
+        Directory { employees_by_department: HashMap::new() }
+    }
+
+    // Adds an employee to a department, starting the department if it's new
+    pub fn add(&mut self, name: &str, department: &str) -> Result<(), DirectoryError> {
+        if name.trim().is_empty() || department.trim().is_empty() {
+            return Err(DirectoryError::EmptyName);
+        }
+        let coworkers = self.employees_by_department.entry(department.to_string()).or_default();
+        match coworkers.binary_search_by(|e| e.as_str().cmp(name)) {
+            Ok(_) => Err(DirectoryError::AlreadyIn { name: name.to_string(), department: department.to_string() }),
+            Err(index) => {
+                coworkers.insert(index, name.to_string());
+                Ok(())
+            },
+        }
+    }
+
+    // Moves an employee from their current department to a new one. Nothing
+    // changes if they can't be moved.
+    pub fn move_to(&mut self, name: &str, from: &str, to: &str) -> Result<(), DirectoryError> {
+        self.position(name, from)?;
+        if to.trim().is_empty() {
+            return Err(DirectoryError::EmptyName);
+        }
+        if self.employees_by_department.get(to).is_some_and(|coworkers| coworkers.iter().any(|e| e == name)) {
+            return Err(DirectoryError::AlreadyIn { name: name.to_string(), department: to.to_string() });
+        }
+
+        self.remove(name, from)?;
+        self.add(name, to)
+    }
+
+    // Removes an employee from a department, and the department when they
+    // were the last one in it
+    pub fn remove(&mut self, name: &str, department: &str) -> Result<(), DirectoryError> {
+        let index = self.position(name, department)?;
+        let employees = self.employees_by_department.get_mut(department).unwrap();
+        employees.remove(index);
+        if employees.is_empty() {
+            self.employees_by_department.remove(department);
+        }
+        Ok(())
+    }
+
+    // Renames an employee in a department
+    pub fn rename(&mut self, name: &str, department: &str, new_name: &str) -> Result<(), DirectoryError> {
+        self.position(name, department)?;
+        if new_name.trim().is_empty() {
+            return Err(DirectoryError::EmptyName);
+        }
+        if name == new_name {
+            return Ok(());
+        }
+        if self.employees_by_department[department].iter().any(|e| e == new_name) {
+            return Err(DirectoryError::AlreadyIn { name: new_name.to_string(), department: department.to_string() });
+        }
+
+        self.remove(name, department)?;
+        self.add(new_name, department)
+    }
+
+    // Every employee in the company and their department, sorted by name
+    // (then department, for people with the same name)
+    pub fn list(&self) -> Vec<(&str, &str)> {
+        let mut employees: Vec<(&str, &str)> = self
+            .employees_by_department
+            .iter()
+            .flat_map(|(department, names)| names.iter().map(move |name| (name.as_str(), department.as_str())))
+            .collect();
+        employees.sort();
+        employees
+    }
+
+    // The employees in a department, sorted
+    pub fn list_department(&self, department: &str) -> Result<&[String], DirectoryError> {
+        match self.employees_by_department.get(department) {
+            Some(employees) => Ok(employees),
+            None => Err(DirectoryError::NoDepartment(department.to_string())),
+        }
+    }
+
+    // Where the employee is in their department's sorted list
+    fn position(&self, name: &str, department: &str) -> Result<usize, DirectoryError> {
+        let employees = match self.employees_by_department.get(department) {
+            Some(employees) => employees,
+            None => return Err(DirectoryError::NoDepartment(department.to_string())),
+        };
+        employees
+            .binary_search_by(|e| e.as_str().cmp(name))
+            .map_err(|_| DirectoryError::NoEmployee { name: name.to_string(), department: department.to_string() })
+    }
+
+    // Runs the text commands (see Command) read from `input` a line at a time
+    // until "Exit" or the end of the input, writing what they print and any
+    // errors to `output`
+    pub fn interpret<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        for line in input.lines() {
+            let command = match Command::parse(&line?) {
+                Ok(command) => command,
+                Err(e) => {
+                    writeln!(output, "{}", e)?;
+                    continue;
+                },
+            };
+
+            let done = match command {
+                Command::Add { name, department } => self.add(&name, &department),
+                Command::Move { name, from, to } => self.move_to(&name, &from, &to),
+                Command::Remove { name, department } => self.remove(&name, &department),
+                Command::Rename { name, department, new_name } => self.rename(&name, &department, &new_name),
+                Command::Print(Some(department)) => match self.list_department(&department) {
+                    Ok(employees) => {
+                        writeln!(output, "Employees in {}: {}", department, employees.join(", "))?;
+                        Ok(())
+                    },
+                    Err(e) => Err(e),
+                },
+                Command::Print(None) => {
+                    let employees: Vec<String> = self.list().iter().map(|(name, department)| format!("{} ({})", name, department)).collect();
+                    writeln!(output, "All employees: {}", employees.join(", "))?;
+                    Ok(())
+                },
+                Command::Help => {
+                    write!(output, "{}", HELP)?;
+                    Ok(())
+                },
+                Command::Exit => break,
+            };
+            if let Err(e) = done {
+                writeln!(output, "Error: {}", e)?;
+            }
+        }
+        output.flush()
+    }
+}
+
+const HELP: &str = "\
+Add Employee:\t\t\t\"Add 'name' to 'department'\"
+Move Employee:\t\t\t\"Move 'name' from 'old department' to 'new department'\"
+Remove Employee:\t\t\"Remove 'name' from 'department'\"
+Rename Employee:\t\t\"Rename 'old name' in 'department' to 'new name'\"
+Print Employees in Department:\t\"Print 'department'\"
+Print All Employees:\t\t\"Print\"
+You don't need to add single quotes around names and departments. They can also be more than one word.
+";
+
+// One line of the text interface
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    // Add _name_ to _department_
+    Add { name: String, department: String },
+    // Move _name_ from _department1_ to _department2_
+    Move { name: String, from: String, to: String },
+    // Remove _name_ from _department_
+    Remove { name: String, department: String },
+    // Rename _name1_ in _department_ to _name2_
+    Rename { name: String, department: String, new_name: String },
+    // Print [_department_]
+    Print(Option<String>),
+    Help,
+    Exit,
+}
+
+impl Command {
+    // Names and departments can be more than one word; the keywords ("to",
+    // "from", "in") say where they end. The error is the message to show.
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some("Add") => Command::Add {
+                name: get_name(&mut words, Some("to"), "employee name")?,
+                department: get_name(&mut words, None, "department name")?,
+            },
+            Some("Move") => Command::Move {
+                name: get_name(&mut words, Some("from"), "employee name")?,
+                from: get_name(&mut words, Some("to"), "current department")?,
+                to: get_name(&mut words, None, "new department")?,
+            },
+            Some("Remove") => Command::Remove {
+                name: get_name(&mut words, Some("from"), "employee name")?,
+                department: get_name(&mut words, None, "department name")?,
+            },
+            Some("Rename") => Command::Rename {
+                name: get_name(&mut words, Some("in"), "employee name")?,
+                department: get_name(&mut words, Some("to"), "department name")?,
+                new_name: get_name(&mut words, None, "new name")?,
+            },
+            Some("Print") => match words.next() {
+                Some(first) => Command::Print(Some(words.fold(first.to_string(), |name, word| name + " " + word))),
+                None => Command::Print(None),
+            },
+            Some("Help") => match words.next() {
+                Some(_) => return Err(String::from("Error: No text should follow \"Help\".")),
+                None => Command::Help,
+            },
+            Some("Exit") => Command::Exit,
+            Some(s) => return Err(unknown_command(s)),
+            None => return Err(String::from("Please enter a command.")),
+        };
+        Ok(command)
+    }
+}
+
+// The words up to `stop` (or the end of the line, without one) joined by
+// spaces. `what` is what they name, for the error message.
+fn get_name(words: &mut std::str::SplitWhitespace, stop: Option<&str>, what: &str) -> Result<String, String> {
+    let mut name: Vec<&str> = Vec::new();
+    loop {
+        match (words.next(), stop) {
+            (Some(word), Some(stop)) if word == stop => break,
+            (Some(word), _) => name.push(word),
+            (None, Some(stop)) => return Err(format!("Error: Expected keyword \"{}\" after {}.", stop, what)),
+            (None, None) => break,
+        }
+    }
+    if name.is_empty() {
+        return Err(format!("Error: Could not find {}.", what));
+    }
+    Ok(name.join(" "))
+}
+
+// Checks if input command is similar to an existing command
+fn unknown_command(txt: &str) -> String {
+    let known = ["Add", "Move", "Remove", "Rename", "Print", "Help", "Exit"];
+    match known.iter().find(|command| command.eq_ignore_ascii_case(txt)) {
+        Some(command) => format!("Error: No command \"{}\" found. Did you mean \"{}\"?", txt, command),
+        None => format!("Error: No command \"{}\" found. Try \"Help\" for a list of commands.", txt),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directory() -> Directory {
+        let mut dir = Directory::new();
+        dir.add("Sally", "Engineering").unwrap();
+        dir.add("Amir", "Sales").unwrap();
+        dir.add("Bob", "Engineering").unwrap();
+        dir
+    }
+
+    fn no_employee(name: &str, department: &str) -> DirectoryError {
+        DirectoryError::NoEmployee { name: name.to_string(), department: department.to_string() }
+    }
+
+    fn already_in(name: &str, department: &str) -> DirectoryError {
+        DirectoryError::AlreadyIn { name: name.to_string(), department: department.to_string() }
+    }
+
+    #[test]
+    fn add_and_list() {
+        let mut dir = directory();
+        assert_eq!(vec![("Amir", "Sales"), ("Bob", "Engineering"), ("Sally", "Engineering")], dir.list());
+        assert_eq!(Ok(&[String::from("Bob"), String::from("Sally")][..]), dir.list_department("Engineering"));
+
+        assert_eq!(Err(already_in("Sally", "Engineering")), dir.add("Sally", "Engineering"));
+        assert_eq!(Err(DirectoryError::EmptyName), dir.add(" ", "Sales"));
+        assert_eq!(Err(DirectoryError::NoDepartment(String::from("Legal"))), dir.list_department("Legal"));
+        // The same name is fine in another department
+        dir.add("Sally", "Sales").unwrap();
+        assert_eq!(("Sally", "Sales"), dir.list()[3]);
+    }
+
+    #[test]
+    fn move_employees() {
+        let mut dir = directory();
+        dir.move_to("Sally", "Engineering", "Marketing").unwrap();
+        assert_eq!(vec![("Amir", "Sales"), ("Bob", "Engineering"), ("Sally", "Marketing")], dir.list());
+
+        // Nothing changes when the move can't be made
+        dir.add("Bob", "Sales").unwrap();
+        assert_eq!(Err(already_in("Bob", "Sales")), dir.move_to("Bob", "Engineering", "Sales"));
+        assert_eq!(Err(no_employee("Amir", "Marketing")), dir.move_to("Amir", "Marketing", "Legal"));
+        assert_eq!(Err(DirectoryError::NoDepartment(String::from("Legal"))), dir.move_to("Amir", "Legal", "Sales"));
+        assert_eq!(4, dir.list().len());
+        assert!(dir.list_department("Legal").is_err());
+
+        // Moving the last employee out closes the department
+        dir.move_to("Bob", "Engineering", "Marketing").unwrap();
+        assert!(dir.list_department("Engineering").is_err());
+    }
+
+    #[test]
+    fn remove_and_rename() {
+        let mut dir = directory();
+        dir.remove("Amir", "Sales").unwrap();
+        assert!(dir.list_department("Sales").is_err());
+        assert_eq!(Err(DirectoryError::NoDepartment(String::from("Sales"))), dir.remove("Amir", "Sales"));
+        assert_eq!(Err(no_employee("Amir", "Engineering")), dir.remove("Amir", "Engineering"));
+
+        dir.rename("Bob", "Engineering", "Zoe").unwrap();
+        assert_eq!(Ok(&[String::from("Sally"), String::from("Zoe")][..]), dir.list_department("Engineering"));
+        assert_eq!(Err(already_in("Sally", "Engineering")), dir.rename("Zoe", "Engineering", "Sally"));
+        assert_eq!(Err(DirectoryError::EmptyName), dir.rename("Zoe", "Engineering", ""));
+        assert_eq!(Ok(()), dir.rename("Zoe", "Engineering", "Zoe"));
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            Ok(Command::Move { name: String::from("Mary Ann"), from: String::from("Human Resources"), to: String::from("Sales") }),
+            Command::parse("Move Mary Ann from Human  Resources to Sales")
+        );
+        assert_eq!(Ok(Command::Print(Some(String::from("Human Resources")))), Command::parse(" Print Human Resources"));
+        assert_eq!(Ok(Command::Print(None)), Command::parse("Print"));
+        assert_eq!(Err(String::from("Error: Expected keyword \"to\" after employee name.")), Command::parse("Add Sally"));
+        assert_eq!(Err(String::from("Error: Could not find department name.")), Command::parse("Add Sally to"));
+        assert_eq!(Err(String::from("Error: No command \"add\" found. Did you mean \"Add\"?")), Command::parse("add Sally to Sales"));
+        assert_eq!(Err(String::from("Please enter a command.")), Command::parse("  "));
+    }
+
+    #[test]
+    fn interpret_commands() {
+        let input = "Add Sally to Engineering\nAdd Amir to Sales\nAdd Sally to Engineering\nPrint Engineering\n\
+                     Rename Amir in Sales to Amira\nPrint\nExit\nPrint\n";
+        let mut output = Vec::new();
+        let mut dir = Directory::new();
+        dir.interpret(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(
+            "Error: Employee with name \"Sally\" already exists in department \"Engineering\".\n\
+             Employees in Engineering: Sally\n\
+             All employees: Amira (Sales), Sally (Engineering)\n",
+            String::from_utf8(output).unwrap()
+        );
+
+        // The end of the input stops it too
+        dir.interpret("Remove Sally from Engineering".as_bytes(), Vec::new()).unwrap();
+        assert_eq!(vec![("Amira", "Sales")], dir.list());
+    }
+}
@@ -1,8 +1,24 @@
-use std::io;
-use rand::Rng;
+use std::{env, io, process};
 use mylib::{stats, pig_latin, department};
+use prompt::{Prompt, PromptError};
 
 fn main() {
+    // `chapter_eight_summary stats [FILE] [--column NAME|N [--against NAME|N]] [--delimiter C]`
+    // summarises numbers from a file, a CSV column or stdin (or relates two
+    // CSV columns) instead of running the exercises
+    let args: Vec<String> = env::args().collect();
+    if args.len() > 1 && args[1] == "stats" {
+        let options = stats::Options::new(&args[2..]).unwrap_or_else(|err| {
+            eprintln!("Problem parsing arguments: {}", err);
+            process::exit(1);
+        });
+        if let Err(e) = stats::run(&options) {
+            eprintln!("Application error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     /*
         The first exercise is to take a vector of numbers and calculate the mean, median, and 
         mode of the vector. I will use a vector of randomly generated integers where the user
@@ -11,52 +27,56 @@ fn main() {
     */
 
     // Getting vector information from user
-    // The number of elements should be type 'usize'
-    let number_elements: usize = stats::get_user_vector_info("How many elements should the vector have:", Option::Some(1), Option::None) as usize;
-    let mut lower_bound;
-    let mut upper_bound;
-    // This loop checks to make sure the lower bound is less than the upper bound
-    loop {
-        lower_bound = stats::get_user_vector_info("Lower bound of numbers:", Option::None, Option::None);
-        upper_bound = stats::get_user_vector_info("Upper bound of numbers:", Option::None, Option::None);
-
-        // the lower_bound should be less than or equal to the upper_bound
-        if lower_bound <= upper_bound {
-            break;
-        }
-    }
+    let (number_elements, lower_bound, upper_bound) = ask_vector_info().unwrap_or_else(|err| {
+        eprintln!("Problem reading the vector information: {}", err);
+        process::exit(1);
+    });
 
-    // Creating the vector using user input
-    let mut numbers: Vec<i32> = Vec::new();
-    for _i in 0..number_elements {
-        numbers.push(rand::thread_rng().gen_range(lower_bound, upper_bound+1));
-    }
+    // Creating the vector using user input. Setting SEED to a number makes
+    // the vector (and the bootstrap intervals below) the same every run.
+    let seed = env::var("SEED").ok().and_then(|seed| seed.parse().ok());
+    let mut rng = stats::rng(seed);
+    let mut numbers: Vec<i32> = stats::random_vector(&mut rng, number_elements, lower_bound, upper_bound);
 
     // Calculate and print mean value of the vector
-    let mean = stats::calc_mean(&numbers);
-    println!("The mean value of the vector is {}", mean);
+    match stats::calc_mean(&numbers) {
+        Some(mean) => println!("The mean value of the vector is {}", mean),
+        None => println!("There is no mean."),
+    };
 
     // Calculate and print the median value of the vector
     let median = stats::calc_median(&numbers);
     median.print();
 
+    // Print the five-number summary and the high percentiles, the way
+    // latencies are usually reported
+    if let Some(five) = stats::five_number_summary(&numbers) {
+        println!("Min: {}, Q1: {}, Median: {}, Q3: {}, Max: {}", five.min, five.lower_quartile, five.median, five.upper_quartile, five.max);
+    }
+    if let Some(p) = stats::quantiles(&numbers, &[0.5, 0.95, 0.99], stats::QuantileMethod::Linear) {
+        println!("p50: {}, p95: {}, p99: {}", p[0], p[1], p[2]);
+    }
+
+    // How far off the mean and median could be, by resampling the vector
+    let bootstrap = stats::Bootstrap::new(2000);
+    if let Some(ci) = bootstrap.bca(&numbers, |v| stats::mean(v).unwrap(), &mut rng) {
+        println!("95% confidence interval for the mean: {} to {}", ci.lower, ci.upper);
+    }
+    if let Some(ci) = bootstrap.percentile(&numbers, |v| stats::median(v).unwrap(), &mut rng) {
+        println!("95% confidence interval for the median: {} to {}", ci.lower, ci.upper);
+    }
+
     // Calculate the mode(s) of the vector
     let modes = stats::calc_mode(&numbers);
     // Printing the mode(s) of the vector
-    if modes.len() == 1 {
-        println!("Mode is: {}", modes[0]);
-    } else {
-        // Formatting the modes string
-        let mut to_print = String::from("Modes are:");
-        for n in modes.iter() {
-            to_print = format!("{} {},", to_print, n);
-        }
-
-        // Remove the last ','
-        to_print.pop();
-        println!("{}", to_print);
-    }
+    modes.print();
     
+    // Printing a histogram of the vector
+    match stats::Histogram::new(&numbers, stats::Bins::Sturges) {
+        Ok(histogram) => println!("Histogram:\n{}", histogram),
+        Err(e) => println!("No histogram: {}", e),
+    };
+
     // sorting the random numbers and printing them
     numbers.sort();
     println!("Numbers: {:?}", numbers);
@@ -74,16 +94,27 @@ fn main() {
 
         I'm not entirely sure how pig latin would work with languages other
         than English, so I'm just going to assume that it works the same
-        but I'm not implementing the vowel case.
+        for anything written with Latin letters (accented vowels are still
+        vowels) and leave other alphabets alone.
     */
 
     println!("\nEnter text to be transformed into pig latin:");
     let mut text = String::new();
     io::stdin().read_line(&mut text).expect("Failed to read line.");
-    let text_in_pig_latin = pig_latin::to_pig_latin(&text);
+    let text_in_pig_latin = pig_latin::to_pig_latin(text.trim_end());
 
     println!("Pig Latin: {}", text_in_pig_latin);
 
+    // And back again. Words starting with a vowel can't be told apart from
+    // the same word with an "h" in front, so those show both.
+    match pig_latin::from_pig_latin(&text_in_pig_latin) {
+        Ok(words) => {
+            let text: String = words.iter().map(|word| word.to_string()).collect();
+            println!("Back to English: {}", text);
+        },
+        Err(e) => println!("Couldn't translate it back: {}", e),
+    };
+
 
 
 
@@ -101,14 +132,32 @@ fn main() {
         Add _name_ to _department_
         Remove _name_ from _department_
         Move _name_ from _department1_ to _department2_
-        Rename _name1_ from _department_ to _name2_
+        Rename _name1_ in _department_ to _name2_
 
     */
     println!("\nUpdate Employee Directory");
     let mut dir = department::Directory::new();
-    loop {
-        if let Some("Exit") = dir.update() {
-            break;
-        }
+    if let Err(e) = dir.interpret(io::stdin().lock(), io::stdout()) {
+        eprintln!("Application error: {}", e);
+        process::exit(1);
     }
-}
\ No newline at end of file
+}
+
+// Asks for the number of elements in the vector and the bounds of its
+// numbers, giving up after a few invalid answers or at the end of the input
+fn ask_vector_info() -> Result<(usize, i32, i32), PromptError> {
+    let mut prompt = Prompt::stdio().attempts(5);
+
+    // The number of elements should be type 'usize'
+    let number_elements = prompt.ask_in_range("How many elements should the vector have:", 1..)?;
+    let lower_bound: i32 = prompt.ask("Lower bound of numbers:")?;
+    // the lower_bound should be less than or equal to the upper_bound
+    let upper_bound = prompt.ask_with("Upper bound of numbers:", |upper: &i32| {
+        if *upper >= lower_bound {
+            Ok(())
+        } else {
+            Err(format!("It has to be at least the lower bound, {}", lower_bound))
+        }
+    })?;
+    Ok((number_elements, lower_bound, upper_bound))
+}
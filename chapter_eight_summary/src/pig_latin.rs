@@ -0,0 +1,735 @@
+use std::error::Error;
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+use unicode_script::{Script, UnicodeScript};
+use unicode_segmentation::UnicodeSegmentation;
+
+// A piece of text: a word to translate, or whatever is between two words
+// (spaces, punctuation, numbers...), which is left exactly as it is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token<'a> {
+    Word(&'a str),
+    Other(&'a str),
+}
+
+// Splits text into words and what's between them. A word is a run of
+// letters. An apostrophe followed by a letter stays in the word once it has
+// at least two letters, so "don't" is one word but in "o'clock" only "clock"
+// is (moving the "o" would leave the word starting with an apostrophe).
+//
+// Pig latin only makes sense for the Latin alphabet, so a run with any
+// other letters in it ("привет", "東京", "Ωmega") isn't a word: it's left as
+// it is, like the spaces and punctuation.
+pub fn tokenize(txt: &str) -> Vec<Token<'_>> {
+    let (indices, graphemes): (Vec<usize>, Vec<&str>) = txt.grapheme_indices(true).unzip();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < graphemes.len() {
+        let end = run_end(&graphemes, i);
+        if end == i || !is_latin(&graphemes[i..end]) {
+            i = end.max(i + 1);
+            continue;
+        }
+
+        // A word starts here
+        let word_start = indices[i];
+        let word_end = indices.get(end).map_or(txt.len(), |index| *index);
+        if start < word_start {
+            tokens.push(Token::Other(&txt[start..word_start]));
+        }
+        tokens.push(Token::Word(&txt[word_start..word_end]));
+        start = word_end;
+        i = end;
+    }
+    if start < txt.len() {
+        tokens.push(Token::Other(&txt[start..]));
+    }
+    tokens
+}
+
+// Where the run of letters (and apostrophes inside it) starting at
+// graphemes[i] ends, or i if there are no letters there
+fn run_end(graphemes: &[&str], i: usize) -> usize {
+    if !graphemes.get(i).is_some_and(|g| is_letter(g)) {
+        return i;
+    }
+    let mut end = i + 1;
+    while end < graphemes.len() {
+        let g = graphemes[end];
+        let apostrophe_in_word = is_apostrophe(g) && end - i >= 2 && graphemes.get(end + 1).is_some_and(|next| is_letter(next));
+        if is_letter(g) || apostrophe_in_word {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+// Whether tokenize() would make a single word of all of `w`
+fn is_word(w: &str) -> bool {
+    match tokenize(w).as_slice() {
+        [Token::Word(token)] => *token == w,
+        _ => false,
+    }
+}
+
+// Whether every letter in the run is a Latin one. Accents don't matter:
+// they're marks, not letters, and "é" is as Latin as "e".
+fn is_latin(run: &[&str]) -> bool {
+    run.iter().filter(|g| is_letter(g)).all(|g| g.chars().next().is_some_and(|c| c.script() == Script::Latin))
+}
+
+pub(crate) fn is_letter(g: &str) -> bool {
+    g.chars().next().is_some_and(char::is_alphabetic)
+}
+
+fn is_apostrophe(g: &str) -> bool {
+    g == "'" || g == "\u{2019}"
+}
+
+// How to speak pig latin. Everyone agrees consonants move to the end and
+// "ay" goes after them, but not on much else.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dialect {
+    // Move every consonant before the first vowel ("string" is
+    // "ing-stray"), not just the first letter ("tring-say")
+    pub clusters: bool,
+    // Move "qu" as one letter: "queen" is "een-quay", not "ueen-qay"
+    pub qu: bool,
+    // Count "y" as a vowel anywhere but at the start of a word, so "rhythm"
+    // is "ythm-rhay" and "yellow" still "ellow-yay". Only matters with
+    // `clusters`, since a "y" at the start is a consonant either way.
+    pub y_vowel: bool,
+    // What words starting with a vowel get
+    pub vowel_suffix: VowelSuffix,
+}
+
+// The ending for words starting with a vowel: "apple" is "apple-way",
+// "apple-yay" or "apple-hay"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VowelSuffix {
+    Way,
+    Yay,
+    Hay,
+}
+
+impl VowelSuffix {
+    // The consonant that goes before the "ay"
+    fn consonant(self) -> &'static str {
+        match self {
+            VowelSuffix::Way => "w",
+            VowelSuffix::Yay => "y",
+            VowelSuffix::Hay => "h",
+        }
+    }
+}
+
+impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect::simple()
+    }
+}
+
+impl Dialect {
+    // How this module always did it: move exactly one letter, and add "h"
+    // in front of vowels before moving it
+    pub fn simple() -> Dialect {
+        Dialect { clusters: false, qu: false, y_vowel: false, vowel_suffix: VowelSuffix::Hay }
+    }
+
+    // The rules most people learn: whole consonant clusters, "qu" together
+    // and "y" as a vowel after the first letter
+    pub fn standard(vowel_suffix: VowelSuffix) -> Dialect {
+        Dialect { clusters: true, qu: true, y_vowel: true, vowel_suffix }
+    }
+
+    pub fn to_pig_latin(&self, txt: &str) -> String {
+        // Translate the words and keep everything around them
+        let mut return_string = String::new();
+        for token in tokenize(txt) {
+            match token {
+                Token::Word(word) => return_string.push_str(&self.translate_word(word)),
+                Token::Other(text) => return_string.push_str(text),
+            }
+        }
+        return_string
+    }
+
+    fn translate_word(&self, word: &str) -> String {
+        keeping_case(word, |word| self.word_to_pig_latin(word))
+    }
+
+    fn word_to_pig_latin(&self, slice: &str) -> String {
+        // This gets the word (slice) as a vector of graphemes to handle any language
+        let graphemes = UnicodeSegmentation::graphemes(slice, true).collect::<Vec<&str>>();
+
+        // Words starting with a vowel keep it and get a consonant at the end
+        // instead; the rest move their first consonant(s) there
+        let moved = self.onset(&graphemes);
+        if moved == 0 {
+            format!("{}-{}ay", slice, self.vowel_suffix.consonant())
+        } else {
+            format!("{}-{}ay", graphemes[moved..].concat(), graphemes[..moved].concat())
+        }
+    }
+
+    // How many graphemes at the start of the word get moved to the end
+    fn onset(&self, graphemes: &[&str]) -> usize {
+        let is = |i: usize, letter: &str| graphemes.get(i).is_some_and(|g| g.to_lowercase() == letter);
+        let consonant = |i: usize| {
+            is_letter(graphemes[i]) && !is_vowel(graphemes[i]) && !(self.y_vowel && i > 0 && is(i, "y"))
+        };
+        if graphemes.is_empty() || is_vowel(graphemes[0]) {
+            return 0;
+        }
+
+        let mut i = 0;
+        while i < graphemes.len() && consonant(i) {
+            i += if self.qu && is(i, "q") && is(i + 1, "u") { 2 } else { 1 };
+            if !self.clusters {
+                break;
+            }
+        }
+        // Don't leave the rest of the word starting with an apostrophe
+        // ("sh'ma" only moves the "s"), or it couldn't be read back
+        while i > 1 && graphemes.get(i).is_some_and(|g| is_apostrophe(g)) {
+            i -= 1;
+        }
+        i
+    }
+}
+
+// Vowels with accents are still vowels. Decomposing the grapheme (NFD)
+// splits "é" into "e" and the accent, so only the letter underneath has to
+// be checked. A few vowels have no letter underneath to find, like "æ" and
+// "ø", so those are listed as they are.
+pub(crate) fn is_vowel(g: &str) -> bool {
+    match g.nfd().next() {
+        Some(c) => "aeiouAEIOUæøœÆØŒ".contains(c),
+        None => false,
+    }
+}
+
+// In the simple dialect
+pub fn to_pig_latin(txt: &str) -> String {
+    Dialect::default().to_pig_latin(txt)
+}
+
+// How a word is capitalized, so the translation can be capitalized the same
+// way. "Hello" becomes "Ello-hay", not "ello-Hay".
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Case {
+    // The first letter is a capital and no other letter is
+    Title,
+    // At least two letters, all capitals
+    Upper,
+    // Anything else (all lowercase, or mixed like "iPhone") is translated
+    // as it is
+    Other,
+}
+
+fn case_of(word: &str) -> Case {
+    let mut letters = word.chars().filter(|c| c.is_alphabetic());
+    let first = match letters.next() {
+        Some(first) => first,
+        None => return Case::Other,
+    };
+    let rest: Vec<char> = letters.collect();
+    if first.is_uppercase() && rest.iter().all(|c| c.is_lowercase()) {
+        Case::Title
+    } else if first.is_uppercase() && !rest.is_empty() && rest.iter().all(|c| c.is_uppercase()) {
+        Case::Upper
+    } else {
+        Case::Other
+    }
+}
+
+fn with_case(word: &str, case: Case) -> String {
+    match case {
+        // The first letter, which isn't always at the start: "b" is "-bay"
+        Case::Title => match word.grapheme_indices(true).find(|(_, g)| is_letter(g)) {
+            Some((i, first)) => format!("{}{}{}", &word[..i], first.to_uppercase(), &word[i + first.len()..]),
+            None => word.to_string(),
+        },
+        Case::Upper => word.to_uppercase(),
+        Case::Other => word.to_string(),
+    }
+}
+
+// Plays a word game on a word in lowercase when it's written in Title case
+// or capitals, then writes the result the same way
+pub(crate) fn keeping_case<F: Fn(&str) -> String>(word: &str, play: F) -> String {
+    match case_of(word) {
+        Case::Other => play(word),
+        case => with_case(&play(&word.to_lowercase()), case),
+    }
+}
+
+// A piece of text translated back from pig latin
+#[derive(Debug, Clone, PartialEq)]
+pub enum Original {
+    Word(String),
+    // More than one word turns into the same pig latin. The consonant added
+    // to words starting with a vowel means "apple-hay" could be "apple" or
+    // "happle", and nothing in the pig latin says which.
+    Ambiguous(Vec<String>),
+    // What was between the words, as it was
+    Other(String),
+}
+
+impl fmt::Display for Original {
+    // Ambiguous words show every option, like "apple/happle"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Original::Word(word) | Original::Other(word) => write!(f, "{}", word),
+            Original::Ambiguous(words) => write!(f, "{}", words.join("/")),
+        }
+    }
+}
+
+// A word that to_pig_latin() couldn't have made
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotPigLatin(pub String);
+
+impl fmt::Display for NotPigLatin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}' isn't pig latin", self.0)
+    }
+}
+
+impl Error for NotPigLatin {}
+
+impl Dialect {
+    // Translates what to_pig_latin() made back. Everything between the words
+    // comes back as it was, so joining the pieces up gives the original text
+    // (with a choice to make for every ambiguous word). Words in lowercase,
+    // Title case or capitals keep their case; odd mixes can't always be told
+    // apart from those, so "bA" comes back as "Ba" (both are "A-bay").
+    pub fn from_pig_latin(&self, txt: &str) -> Result<Vec<Original>, NotPigLatin> {
+        let graphemes: Vec<&str> = txt.graphemes(true).collect();
+        let n = graphemes.len();
+
+        // Runs with letters from other alphabets were left as they were. A
+        // pig latin word can end up in one of those runs, as in "å-yay'хь",
+        // so only the other alphabets' letters can't be part of a word.
+        let mut foreign = vec![false; n];
+        let mut i = 0;
+        while i < n {
+            let end = run_end(&graphemes, i);
+            if !is_latin(&graphemes[i..end]) {
+                foreign[i..end].iter_mut().for_each(|f| *f = true);
+            }
+            i = end.max(i + 1);
+        }
+
+        // A "-" can be part of what was between words, or the start of a one
+        // letter word ("b" is "-bay"), and sometimes which one only shows
+        // further on. So work back from the end: reads[i] is how to read
+        // graphemes[i..] as pig latin, taking a word there if that works, or
+        // None if they can't be read at all.
+        let mut reads: Vec<Option<Read>> = vec![None; n + 1];
+        reads[n] = Some(Read::End);
+        for i in (0..n).rev() {
+            if let Some(len) = word_len_at(&graphemes, i) {
+                if reads[i + len].is_some() {
+                    if let Ok(original) = self.word_from_pig_latin(&graphemes[i..i + len].concat()) {
+                        reads[i] = Some(Read::Word(len, original));
+                        continue;
+                    }
+                }
+            }
+            if (!is_letter(graphemes[i]) || foreign[i]) && reads[i + 1].is_some() {
+                reads[i] = Some(Read::Other);
+            }
+        }
+
+        if reads[0].is_none() {
+            // Every grapheme before the bad word has no reading either, so
+            // go forward the same way instead: reached[i] is whether
+            // graphemes[..i] can be read. The furthest any reading gets is
+            // a letter that starts the first run that isn't a word.
+            let mut reached = vec![false; n + 1];
+            reached[0] = true;
+            for i in 0..n {
+                if !reached[i] {
+                    continue;
+                }
+                if !is_letter(graphemes[i]) || foreign[i] {
+                    reached[i + 1] = true;
+                }
+                if let Some(len) = word_len_at(&graphemes, i) {
+                    if self.word_from_pig_latin(&graphemes[i..i + len].concat()).is_ok() {
+                        reached[i + len] = true;
+                    }
+                }
+            }
+            let stuck = (0..n).rev().find(|i| reached[*i]).unwrap_or(0);
+            let len = graphemes[stuck..].iter().take_while(|g| is_letter(g)).count();
+            return Err(NotPigLatin(graphemes[stuck..stuck + len.max(1)].concat()));
+        }
+
+        let mut originals = Vec::new();
+        let mut other = String::new();
+        let mut i = 0;
+        while i < n {
+            match reads[i].take() {
+                Some(Read::Word(len, original)) => {
+                    if !other.is_empty() {
+                        originals.push(Original::Other(std::mem::take(&mut other)));
+                    }
+                    originals.push(original);
+                    i += len;
+                },
+                _ => {
+                    other.push_str(graphemes[i]);
+                    i += 1;
+                },
+            }
+        }
+        if !other.is_empty() {
+            originals.push(Original::Other(other));
+        }
+        Ok(originals)
+    }
+
+    fn word_from_pig_latin(&self, word: &str) -> Result<Original, NotPigLatin> {
+        // Capitalized words were translated in lowercase, so translate them
+        // back in lowercase too. Only if that can't work was the word mixed
+        // case.
+        let mut words = match case_of(word) {
+            Case::Other => Vec::new(),
+            case => self.untranslate(&word.to_lowercase()).iter().map(|w| with_case(w, case)).collect(),
+        };
+        words.retain(|w| is_word(w) && self.translate_word(w) == word);
+        if words.is_empty() {
+            words = self.untranslate(word);
+            words.retain(|w| is_word(w) && self.translate_word(w) == word);
+        }
+
+        words.sort();
+        words.dedup();
+        match words.len() {
+            0 => Err(NotPigLatin(word.to_string())),
+            1 => Ok(Original::Word(words.remove(0))),
+            _ => Ok(Original::Ambiguous(words)),
+        }
+    }
+
+    // Every word word_to_pig_latin() might have turned into `word`
+    fn untranslate(&self, word: &str) -> Vec<String> {
+        let (rest, moved) = match word.strip_suffix("ay").and_then(|w| w.rsplit_once('-')) {
+            Some(split) => split,
+            None => return Vec::new(),
+        };
+
+        let mut words = vec![format!("{}{}", moved, rest)];
+        // The consonant might have been added after a vowel
+        if moved == self.vowel_suffix.consonant() && !rest.is_empty() {
+            words.push(rest.to_string());
+        }
+        words.retain(|w| self.word_to_pig_latin(w) == word);
+        words
+    }
+}
+
+// In the simple dialect
+pub fn from_pig_latin(txt: &str) -> Result<Vec<Original>, NotPigLatin> {
+    Dialect::default().from_pig_latin(txt)
+}
+
+// One step of reading pig latin back
+#[derive(Debug, Clone)]
+enum Read {
+    // A word this many graphemes long
+    Word(usize, Original),
+    // One grapheme of what was between the words
+    Other,
+    End,
+}
+
+// The length of the pig latin word that could start at graphemes[i]. A
+// word can't start in the middle of another, or have letters from another
+// alphabet in it.
+fn word_len_at(graphemes: &[&str], i: usize) -> Option<usize> {
+    if i > 0 && is_letter(graphemes[i - 1]) {
+        return None;
+    }
+    pig_latin_word_len(&graphemes[i..]).filter(|len| is_latin(&graphemes[i..i + len]))
+}
+
+// How many graphemes the pig latin word at the start of `graphemes` takes
+// up: the rest of the word, a "-", the letter(s) that were moved and "ay"
+fn pig_latin_word_len(graphemes: &[&str]) -> Option<usize> {
+    let mut i = 0;
+    while i < graphemes.len() && (is_letter(graphemes[i]) || (i > 0 && is_apostrophe(graphemes[i]))) {
+        i += 1;
+    }
+    if graphemes.get(i) != Some(&"-") {
+        return None;
+    }
+    let start = i + 1;
+    let end = start + graphemes[start..].iter().take_while(|g| is_letter(g)).count();
+    // At least one letter was moved, and the word ends right after the "ay"
+    if end - start < 3 {
+        return None;
+    }
+    match (graphemes[end - 2], graphemes[end - 1]) {
+        ("a", "y") | ("A", "Y") => Some(end),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn word(w: &str) -> Original {
+        Original::Word(w.to_string())
+    }
+
+    fn other(text: &str) -> Original {
+        Original::Other(text.to_string())
+    }
+
+    fn either(a: &str, b: &str) -> Original {
+        Original::Ambiguous(vec![a.to_string(), b.to_string()])
+    }
+
+    #[test]
+    fn keeps_everything_between_words() {
+        assert_eq!("Ello-hay, orld-way!", to_pig_latin("Hello, world!"));
+        assert_eq!("  irst-fay\n\tapple-hay.\n", to_pig_latin("  first\n\tapple.\n"));
+        assert_eq!("", to_pig_latin(""));
+        assert_eq!("42 -- ?", to_pig_latin("42 -- ?"));
+    }
+
+    #[test]
+    fn keeps_capitals() {
+        assert_eq!("Ig-pay ATIN-LAY", to_pig_latin("Pig LATIN"));
+        assert_eq!("Apple-hay I-hay", to_pig_latin("Apple I"));
+        // Mixed case is translated as it is
+        assert_eq!("iPhone-hay", to_pig_latin("iPhone"));
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            vec![Token::Word("Don't"), Token::Other(" "), Token::Word("stop"), Token::Other("'")],
+            tokenize("Don't stop'")
+        );
+        assert_eq!(vec![Token::Word("o"), Token::Other("'"), Token::Word("clock")], tokenize("o'clock"));
+        assert_eq!(vec![Token::Word("well"), Token::Other("-"), Token::Word("known")], tokenize("well-known"));
+        assert_eq!("on't-day", to_pig_latin("don't"));
+    }
+
+    #[test]
+    fn back_to_english() {
+        assert_eq!(Ok(vec![word("first"), other(" "), word("Pig")]), from_pig_latin("irst-fay Ig-pay"));
+        assert_eq!(
+            Ok(vec![word("Good"), other(", "), word("world"), other("!")]),
+            from_pig_latin("Ood-gay, orld-way!")
+        );
+        assert_eq!(Ok(vec![word("B"), other("'")]), from_pig_latin("-Bay'"));
+        assert_eq!(Ok(vec![word("LATIN")]), from_pig_latin("ATIN-LAY"));
+        // A consonant after the "h" means it was there all along
+        assert_eq!(Ok(vec![word("hppy")]), from_pig_latin("ppy-hay"));
+    }
+
+    #[test]
+    fn vowels_are_ambiguous() {
+        assert_eq!(Ok(vec![either("apple", "happle")]), from_pig_latin("apple-hay"));
+        assert_eq!(Ok(vec![either("Eat", "Heat")]), from_pig_latin("Eat-hay"));
+        assert_eq!("apple/happle", either("apple", "happle").to_string());
+    }
+
+    #[test]
+    fn hyphens_and_graphemes() {
+        assert_eq!("ell-way-nown-kay", to_pig_latin("well-known"));
+        assert_eq!(
+            Ok(vec![word("well"), other("-"), word("known")]),
+            from_pig_latin("ell-way-nown-kay")
+        );
+        assert_eq!(Ok(vec![word("b"), other("-"), word("c")]), from_pig_latin("-bay--cay"));
+        // The "-" here is between words, since "pay" on its own isn't pig latin
+        assert_eq!(Ok(vec![other("-"), word("play")]), from_pig_latin("-lay-pay"));
+        assert_eq!(
+            Ok(vec![word("b"), other("-"), word("play")]),
+            from_pig_latin("-bay-lay-pay")
+        );
+        assert_eq!(Ok(vec![word("ça")]), from_pig_latin("a-çay"));
+        assert_eq!(Ok(vec![word("n\u{303}u")]), from_pig_latin("u-n\u{303}ay"));
+    }
+
+    #[test]
+    fn accented_vowels() {
+        assert_eq!("éclair-hay Über-hay åker-hay øl-hay", to_pig_latin("éclair Über åker øl"));
+        // The same with the accents as separate characters
+        assert_eq!("e\u{301}clair-hay", to_pig_latin("e\u{301}clair"));
+        assert_eq!("aïve-nay", to_pig_latin("naïve"));
+        assert_eq!(
+            "ümlich-gray",
+            Dialect::standard(VowelSuffix::Way).to_pig_latin("grümlich")
+        );
+        assert_eq!(Ok(vec![either("Héclair", "Éclair")]), from_pig_latin("Éclair-hay"));
+    }
+
+    #[test]
+    fn other_alphabets() {
+        let text = "Привет, мир! 東京 καλημέρα";
+        assert_eq!(text, to_pig_latin(text));
+        assert_eq!(Ok(vec![other(text)]), from_pig_latin(text));
+
+        // A word with any letters from another alphabet is left alone too
+        assert_eq!(vec![Token::Other("Ωmega "), Token::Word("pig")], tokenize("Ωmega pig"));
+        assert_eq!("Ωmega ig-pay", to_pig_latin("Ωmega pig"));
+        assert_eq!(Ok(vec![other("Ωmega "), word("pig")]), from_pig_latin("Ωmega ig-pay"));
+        assert!(from_pig_latin("東-bay").is_err());
+    }
+
+    #[test]
+    fn not_pig_latin() {
+        assert_eq!(Err(NotPigLatin(String::from("hello"))), from_pig_latin("ello-hay hello"));
+        // "-bay" is "b", so the word that isn't pig latin comes after it
+        assert_eq!(Err(NotPigLatin(String::from("hello"))), from_pig_latin("-bay hello"));
+        assert_eq!(Err(NotPigLatin(String::from("hello"))), from_pig_latin("ello-hay -bay hello"));
+        assert_eq!(Err(NotPigLatin(String::from("hello"))), from_pig_latin("hello -bay world"));
+        assert!(from_pig_latin("ello-hy").is_err());
+        assert!(from_pig_latin("ello-ay").is_err());
+        assert!(from_pig_latin("ello-hayx").is_err());
+        assert_eq!(Ok(vec![other("  ")]), from_pig_latin("  "));
+    }
+
+    #[test]
+    fn simple_dialect() {
+        let simple = Dialect::simple();
+        assert_eq!(simple, Dialect::default());
+        assert_eq!(
+            "tring-say ueen-qay hythm-ray ellow-yay apple-hay",
+            simple.to_pig_latin("string queen rhythm yellow apple")
+        );
+    }
+
+    #[test]
+    fn standard_dialect() {
+        let standard = Dialect::standard(VowelSuffix::Way);
+        assert_eq!("ing-stray een-quay are-squay", standard.to_pig_latin("string queen square"));
+        assert_eq!("ythm-rhay y-may ellow-yay", standard.to_pig_latin("rhythm my yellow"));
+        assert_eq!("Apple-way, Ing-stray!", standard.to_pig_latin("Apple, String!"));
+        // Only letters move, so the rest of the word doesn't start with the
+        // apostrophe
+        assert_eq!("h'ma-say", standard.to_pig_latin("sh'ma"));
+
+        assert_eq!(Ok(vec![word("string")]), standard.from_pig_latin("ing-stray"));
+        assert_eq!(Ok(vec![word("Queen")]), standard.from_pig_latin("Een-quay"));
+        assert_eq!(Ok(vec![word("yellow")]), standard.from_pig_latin("ellow-yay"));
+        assert_eq!(Ok(vec![either("apple", "wapple")]), standard.from_pig_latin("apple-way"));
+        // The simple dialect only ever moves one letter
+        assert!(Dialect::simple().from_pig_latin("ing-stray").is_err());
+    }
+
+    #[test]
+    fn vowel_suffixes() {
+        let yay = Dialect::standard(VowelSuffix::Yay);
+        assert_eq!("apple-yay ing-stray", yay.to_pig_latin("apple string"));
+        assert_eq!(Ok(vec![either("apple", "yapple")]), yay.from_pig_latin("apple-yay"));
+        // "yellow" moves its "y" like any consonant, so it looks just like
+        // "ellow" with the vowel suffix
+        assert_eq!(Ok(vec![either("ellow", "yellow")]), yay.from_pig_latin("ellow-yay"));
+
+        let hay = Dialect::standard(VowelSuffix::Hay);
+        assert_eq!("apple-hay ing-stray", hay.to_pig_latin("apple string"));
+        assert_eq!(Ok(vec![word("yellow")]), hay.from_pig_latin("ellow-yay"));
+    }
+
+    #[test]
+    fn each_rule_on_its_own() {
+        let clusters = Dialect { clusters: true, ..Dialect::simple() };
+        assert_eq!("ing-stray ueen-qay -rhythmay", clusters.to_pig_latin("string queen rhythm"));
+        assert_eq!(Ok(vec![word("rhythm")]), clusters.from_pig_latin("-rhythmay"));
+
+        let qu = Dialect { qu: true, ..Dialect::simple() };
+        assert_eq!("een-quay quare-say", qu.to_pig_latin("queen square"));
+
+        // "y" as a vowel only changes where a cluster stops
+        let y = Dialect { y_vowel: true, ..Dialect::simple() };
+        assert_eq!("hythm-ray", y.to_pig_latin("rhythm"));
+        let y = Dialect { y_vowel: true, ..clusters };
+        assert_eq!("ythm-rhay ellow-yay", y.to_pig_latin("rhythm yellow"));
+    }
+
+    fn dialect() -> impl Strategy<Value = Dialect> {
+        let suffix = prop_oneof![Just(VowelSuffix::Way), Just(VowelSuffix::Yay), Just(VowelSuffix::Hay)];
+        (any::<bool>(), any::<bool>(), any::<bool>(), suffix).prop_map(|(clusters, qu, y_vowel, vowel_suffix)| Dialect {
+            clusters,
+            qu,
+            y_vowel,
+            vowel_suffix,
+        })
+    }
+
+    // Words written in lowercase, Title case or ALL CAPS, with some
+    // punctuation and whitespace between them, and now and then a word in
+    // another alphabet
+    fn text() -> impl Strategy<Value = String> {
+        let word = ("[a-zéüåøç]{1,8}", 0..4, "[α-ωа-я]{1,8}").prop_map(|(word, case, foreign)| match case {
+            0 => word,
+            1 => with_case(&word, Case::Title),
+            2 => word.to_uppercase(),
+            // Kept apart from the word before, since with an apostrophe
+            // between them "-cay'αβ" could be "c'αβ" or be left as it was
+            _ => format!(" {}", foreign),
+        });
+        prop::collection::vec((word, "[ ,.!?'\n\t-]{1,3}"), 0..8)
+            .prop_map(|pieces| pieces.into_iter().map(|(word, between)| word + &between).collect())
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(text in text(), dialect in dialect()) {
+            let originals = dialect.from_pig_latin(&dialect.to_pig_latin(&text)).unwrap();
+            let tokens = tokenize(&text);
+            prop_assert_eq!(tokens.len(), originals.len());
+
+            for (token, original) in tokens.iter().zip(originals.iter()) {
+                match (token, original) {
+                    (Token::Word(w), Original::Word(o)) => prop_assert_eq!(w, o),
+                    (Token::Word(w), Original::Ambiguous(os)) => {
+                        prop_assert!(os.iter().any(|o| o == w));
+                        for o in os.iter() {
+                            prop_assert_eq!(dialect.translate_word(w), dialect.translate_word(o));
+                        }
+                    },
+                    (Token::Other(t), Original::Other(o)) => prop_assert_eq!(t, o),
+                    _ => prop_assert!(false, "{:?} came back as {:?}", token, original),
+                }
+            }
+        }
+
+        #[test]
+        fn round_trip_any_word(word in "[\\p{Latin}&&\\p{Ll}]{1,8}", dialect in dialect()) {
+            match dialect.word_from_pig_latin(&dialect.translate_word(&word)).unwrap() {
+                Original::Word(w) => prop_assert_eq!(word, w),
+                Original::Ambiguous(ws) => prop_assert!(ws.contains(&word)),
+                Original::Other(_) => prop_assert!(false),
+            }
+        }
+
+        #[test]
+        fn other_alphabets_pass_through(text in "[\\p{Greek}\\p{Cyrillic}\\p{Han}\\p{Hangul} ,.!'-]{1,20}") {
+            prop_assert_eq!(&text, &to_pig_latin(&text));
+            prop_assert_eq!(Ok(vec![Original::Other(text.clone())]), from_pig_latin(&text));
+        }
+
+        // Only a word starting with a vowel, or with a lowercase h and then
+        // a vowel, can be mistaken for another
+        #[test]
+        fn consonant_words_are_not_ambiguous(word in "[b-df-gj-np-tv-z][a-z]{0,7}") {
+            let simple = Dialect::simple();
+            prop_assert_eq!(Original::Word(word.clone()), simple.word_from_pig_latin(&simple.translate_word(&word)).unwrap());
+        }
+    }
+}
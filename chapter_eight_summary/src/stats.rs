@@ -0,0 +1,239 @@
+pub use std::io;
+use std::fmt::Display;
+use std::hash::Hash;
+
+// Single-pass statistics for any kind of number
+mod bootstrap;
+mod cli;
+mod histogram;
+mod hypothesis;
+mod input;
+mod mean;
+mod paired;
+mod quantile;
+mod series;
+mod sketch;
+mod special;
+mod summary;
+
+pub use self::bootstrap::{random_vector, rng, Bootstrap, ConfidenceInterval};
+pub use self::cli::{report, report_pairs, run, Options};
+pub use self::histogram::{frequencies, Bins, Histogram};
+pub use self::hypothesis::{chi_square_test, mann_whitney_u, one_sample_t_test, welch_t_test, TestResult};
+pub use self::input::{read_csv_column, read_csv_pairs, read_numbers, Column, Numbers, Pairs, Skipped};
+pub use self::mean::mean;
+pub use self::paired::{covariance, linear_regression, pearson, spearman, LinearFit};
+pub use self::quantile::{five_number_summary, iqr, median, percentile, quantile, quantiles, FiveNumberSummary, QuantileMethod};
+pub use self::series::{Ewma, MovingAverage, Outliers, RollingExtreme, RollingStd, Scored, Series, WeightedMovingAverage};
+pub use self::sketch::QuantileSketch;
+pub use self::special::{incomplete_beta, incomplete_gamma, incomplete_gamma_upper, ln_gamma, normal_cdf, normal_quantile};
+pub use self::summary::{Sample, Summary};
+
+#[derive(Debug, PartialEq)]
+pub enum Median {
+    None,
+    One(i32),
+    Two(i32,i32),
+}
+
+impl Median {
+    pub fn print(&self) {
+        match self {
+            Median::None => println!("There is no median."),
+            Median::One(i) => println!("Median value is: {}", i),
+            Median::Two(i,j) => println!("Median values are: {} and {}", i, j),
+        };
+    }
+}
+
+// The most common value(s), and how many times they show up
+#[derive(Debug, PartialEq)]
+pub enum Mode<T> {
+    // There's nothing to take the mode of
+    None,
+    // Every value shows up the same number of times, so no value stands out
+    Uniform(usize),
+    Unimodal(T, usize),
+    // More than one value ties for most common, smallest first
+    Multimodal(Vec<T>, usize),
+}
+
+impl<T: Display> Mode<T> {
+    pub fn print(&self) {
+        match self {
+            Mode::None => println!("There is no mode."),
+            Mode::Uniform(freq) => println!("There is no mode, every value shows up {} time(s).", freq),
+            Mode::Unimodal(m, freq) => println!("Mode is: {} ({} times)", m, freq),
+            Mode::Multimodal(modes, freq) => {
+                let modes: Vec<String> = modes.iter().map(|m| m.to_string()).collect();
+                println!("Modes are: {} ({} times each)", modes.join(", "), freq);
+            },
+        };
+    }
+}
+
+pub fn calc_mean(v: &[i32]) -> Option<f64> {
+    // An empty vector has no mean (dividing by 0 would give NaN)
+    if v.is_empty() {
+        return None;
+    }
+
+    // CALCULATING THE MEAN
+    // Adding up i32s in an i32 overflows quickly, so add them up in an
+    // i128. That's exact, since no Vec could ever hold enough i32s to
+    // overflow it.
+    let mut sum: i128 = 0;
+    for arr in v.iter() {
+        sum += i128::from(*arr);
+    }
+    
+    // Average should be a float, not an integer
+    Some(sum as f64 / v.len() as f64)
+}
+
+pub fn calc_median(v: &[i32]) -> Median {
+    let number_elements = v.len();
+    if number_elements == 0 {
+        return Median::None;
+    }
+
+    // Sorting the whole vector just to look at the middle of it is a lot of
+    // wasted work. quantile::select() only puts the middle value in place.
+    let mut numbers = v.to_vec();
+    let index = number_elements/2;
+    let middle = quantile::select(&mut numbers, index);
+
+    // If number_elements % 2 == 1 there is always 1 median, otherwise 
+    // there could be 2 values
+    if number_elements%2 == 1 {
+        return Median::One(middle);
+    }
+
+    // The other middle value is the largest of the ones select() put
+    // before the middle
+    let before = numbers[..index].iter().copied().max().unwrap();
+
+    // Get the two elements for the median and check if they are the
+    // same. If same, median is just that number. If different, both
+    // numbers represent the median
+    if before == middle {
+        Median::One(middle)
+    } else {
+        Median::Two(before, middle)
+    }
+}
+
+pub fn calc_mode<T: Hash + Ord + Clone>(v: &[T]) -> Mode<T> {
+    // How many times each value shows up
+    let number_frequency = frequencies(v);
+
+    let max_freq = match number_frequency.values().max() {
+        Some(freq) => *freq,
+        None => return Mode::None,
+    };
+
+    // A HashMap gives back its keys in a different order every run, so
+    // sort the modes to always report them the same way
+    let mut modes: Vec<&T> = number_frequency
+        .iter()
+        .filter(|(_, freq)| **freq == max_freq)
+        .map(|(num, _)| *num)
+        .collect();
+    modes.sort();
+
+    if modes.len() == 1 {
+        Mode::Unimodal(modes[0].clone(), max_freq)
+    } else if modes.len() == number_frequency.len() {
+        Mode::Uniform(max_freq)
+    } else {
+        Mode::Multimodal(modes.into_iter().cloned().collect(), max_freq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+    use num_traits::ToPrimitive;
+    use proptest::prelude::*;
+
+    #[test]
+    fn mean_of_nothing() {
+        assert_eq!(None, calc_mean(&[]));
+    }
+
+    #[test]
+    fn mean_does_not_overflow() {
+        assert_eq!(Some(f64::from(i32::MAX)), calc_mean(&[i32::MAX; 4]));
+        assert_eq!(Some(f64::from(i32::MIN)), calc_mean(&[i32::MIN; 4]));
+        assert_eq!(Some(-0.5), calc_mean(&[i32::MAX, i32::MIN]));
+    }
+
+    fn sorted_median(v: &[i32]) -> Median {
+        let mut sorted = v.to_vec();
+        sorted.sort();
+        let n = sorted.len();
+        match n {
+            0 => Median::None,
+            _ if n % 2 == 1 || sorted[n / 2 - 1] == sorted[n / 2] => Median::One(sorted[n / 2]),
+            _ => Median::Two(sorted[n / 2 - 1], sorted[n / 2]),
+        }
+    }
+
+    #[test]
+    fn median_without_sorting() {
+        assert_eq!(Median::None, calc_median(&[]));
+        assert_eq!(Median::One(4), calc_median(&[9, 4, 1]));
+        assert_eq!(Median::Two(3, 5), calc_median(&[5, 9, 1, 3]));
+        assert_eq!(Median::One(2), calc_median(&[2, 7, 2, 0]));
+    }
+
+    #[test]
+    fn mode_of_nothing() {
+        assert_eq!(Mode::None, calc_mode::<i32>(&[]));
+    }
+
+    #[test]
+    fn one_mode() {
+        assert_eq!(Mode::Unimodal(3, 3), calc_mode(&[1, 3, 2, 3, 3, 2]));
+        // One distinct value is still a single mode
+        assert_eq!(Mode::Unimodal(7, 2), calc_mode(&[7, 7]));
+    }
+
+    #[test]
+    fn many_modes_are_sorted() {
+        assert_eq!(Mode::Multimodal(vec![-4, 2, 9], 2), calc_mode(&[9, 2, 1, -4, 9, 2, 5, -4]));
+    }
+
+    #[test]
+    fn uniform_data_has_no_mode() {
+        assert_eq!(Mode::Uniform(1), calc_mode(&[4, 1, 3, 2]));
+        assert_eq!(Mode::Uniform(2), calc_mode(&[1, 2, 1, 2]));
+    }
+
+    #[test]
+    fn modes_of_strings() {
+        let words: Vec<String> = "the cat and the dog and the bird".split(' ').map(String::from).collect();
+        assert_eq!(Mode::Unimodal(String::from("the"), 3), calc_mode(&words));
+        assert_eq!(Mode::Multimodal(vec!["a", "b"], 2), calc_mode(&["b", "a", "c", "a", "b"]));
+    }
+
+    proptest! {
+        #[test]
+        fn median_matches_sorted_median(v in prop::collection::vec(-50i32..50, 0..200)) {
+            prop_assert_eq!(sorted_median(&v), calc_median(&v));
+        }
+
+        #[test]
+        fn mean_matches_exact_mean(v in prop::collection::vec(any::<i32>(), 1..500)) {
+            let sum: BigInt = v.iter().map(|x| BigInt::from(*x)).sum();
+            let exact = BigRational::new(sum, BigInt::from(v.len())).to_f64().unwrap();
+            let actual = calc_mean(&v).unwrap();
+
+            // The sum is exact, so only turning it into an f64 and the
+            // division round
+            prop_assert!((exact - actual).abs() <= 2.0 * f64::EPSILON * exact.abs(), "expected {}, got {}", exact, actual);
+        }
+    }
+}
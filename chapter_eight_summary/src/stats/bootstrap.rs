@@ -0,0 +1,283 @@
+use rand::rngs::StdRng;
+use rand::{FromEntropy, Rng, SeedableRng};
+
+use super::quantile::{quantiles, QuantileMethod};
+use super::special::{normal_cdf, normal_quantile};
+
+// The bootstrap works out how much a statistic could be off by resampling:
+// draw n values from the n we have (with replacement) many times over,
+// work the statistic out for every draw and see how much it moves around.
+// It works for any statistic, not just the ones with a formula for their
+// standard error, which is why the statistic is a closure.
+
+// A random number generator for everything random in `stats`. The same seed
+// always gives the same numbers, so runs (and tests) can be repeated; with
+// no seed it's seeded from the operating system.
+pub fn rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+// `n` random whole numbers from lower to upper, both included. The range is
+// widened to i64 so an upper bound of i32::MAX doesn't overflow.
+pub fn random_vector<R: Rng>(rng: &mut R, n: usize, lower: i32, upper: i32) -> Vec<i32> {
+    (0..n).map(|_| rng.gen_range(i64::from(lower), i64::from(upper) + 1) as i32).collect()
+}
+
+// A range the true value of a statistic falls in, `confidence` of the time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceInterval {
+    // The statistic of the values themselves
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub confidence: f64,
+}
+
+// How to bootstrap: how many resamples to draw and how confident the
+// intervals should be
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bootstrap {
+    resamples: usize,
+    confidence: f64,
+}
+
+impl Bootstrap {
+    // 95% intervals. A couple of thousand resamples is plenty for those;
+    // intervals further out in the tails need more.
+    pub fn new(resamples: usize) -> Bootstrap {
+        Bootstrap { resamples, confidence: 0.95 }
+    }
+
+    pub fn confidence(mut self, confidence: f64) -> Bootstrap {
+        self.confidence = confidence;
+        self
+    }
+
+    // The percentile interval: the middle `confidence` of the resampled
+    // statistics. Simple, but off when the statistic is biased or its
+    // spread depends on the value, as with skewed data.
+    //
+    // None for no values, no resamples or a confidence that isn't between 0
+    // and 1, or if the statistic gives NaN.
+    pub fn percentile<T, F, R>(&self, values: &[T], statistic: F, rng: &mut R) -> Option<ConfidenceInterval>
+    where
+        T: Copy,
+        F: Fn(&[T]) -> f64,
+        R: Rng,
+    {
+        let (estimate, resampled) = self.resample(values, &statistic, rng)?;
+        let alpha = (1.0 - self.confidence) / 2.0;
+        self.interval(estimate, &resampled, alpha, 1.0 - alpha)
+    }
+
+    // The bias-corrected and accelerated (BCa) interval. It moves the
+    // percentiles of the percentile interval to make up for the statistic's
+    // bias (how many resamples came out below the estimate) and for how its
+    // spread changes with the value (the acceleration, from a jackknife:
+    // leaving each value out in turn). Needs at least two values and
+    // otherwise gives None in the same cases as percentile(), or when every
+    // resample lands on the same side of the estimate.
+    pub fn bca<T, F, R>(&self, values: &[T], statistic: F, rng: &mut R) -> Option<ConfidenceInterval>
+    where
+        T: Copy,
+        F: Fn(&[T]) -> f64,
+        R: Rng,
+    {
+        if values.len() < 2 {
+            return None;
+        }
+        let (estimate, resampled) = self.resample(values, &statistic, rng)?;
+
+        // The bias correction: 0 when half the resamples are below the
+        // estimate. Ties count half on each side.
+        let below = resampled.iter().filter(|s| **s < estimate).count() as f64;
+        let equal = resampled.iter().filter(|s| **s == estimate).count() as f64;
+        let z0 = normal_quantile((below + equal / 2.0) / resampled.len() as f64);
+        if z0.is_infinite() {
+            return None;
+        }
+
+        let a = acceleration(values, &statistic)?;
+        let adjust = |alpha: f64| {
+            let z = z0 + normal_quantile(alpha);
+            normal_cdf(z0 + z / (1.0 - a * z))
+        };
+        let alpha = (1.0 - self.confidence) / 2.0;
+        self.interval(estimate, &resampled, adjust(alpha), adjust(1.0 - alpha))
+    }
+
+    // The statistic of the values and of every resample
+    fn resample<T, F, R>(&self, values: &[T], statistic: &F, rng: &mut R) -> Option<(f64, Vec<f64>)>
+    where
+        T: Copy,
+        F: Fn(&[T]) -> f64,
+        R: Rng,
+    {
+        if values.is_empty() || self.resamples == 0 || !(self.confidence > 0.0 && self.confidence < 1.0) {
+            return None;
+        }
+        let estimate = statistic(values);
+        if estimate.is_nan() {
+            return None;
+        }
+
+        let mut draw = Vec::with_capacity(values.len());
+        let mut resampled = Vec::with_capacity(self.resamples);
+        for _ in 0..self.resamples {
+            draw.clear();
+            draw.extend((0..values.len()).map(|_| values[rng.gen_range(0, values.len())]));
+            let s = statistic(&draw);
+            if s.is_nan() {
+                return None;
+            }
+            resampled.push(s);
+        }
+        Some((estimate, resampled))
+    }
+
+    fn interval(&self, estimate: f64, resampled: &[f64], lower: f64, upper: f64) -> Option<ConfidenceInterval> {
+        let bounds = quantiles(resampled, &[lower, upper], QuantileMethod::Linear)?;
+        Some(ConfidenceInterval { estimate, lower: bounds[0], upper: bounds[1], confidence: self.confidence })
+    }
+}
+
+// How fast the statistic's standard error changes with its value, from the
+// skewness of the jackknife statistics. 0 when leaving any one value out
+// moves the statistic the same amount either way.
+fn acceleration<T: Copy, F: Fn(&[T]) -> f64>(values: &[T], statistic: &F) -> Option<f64> {
+    let mut left_out = Vec::with_capacity(values.len() - 1);
+    let mut jackknife = Vec::with_capacity(values.len());
+    for i in 0..values.len() {
+        left_out.clear();
+        left_out.extend_from_slice(&values[..i]);
+        left_out.extend_from_slice(&values[i + 1..]);
+        jackknife.push(statistic(&left_out));
+    }
+    let mean = jackknife.iter().sum::<f64>() / jackknife.len() as f64;
+
+    let mut squares = 0.0;
+    let mut cubes = 0.0;
+    for s in jackknife.iter() {
+        let d = mean - s;
+        squares += d * d;
+        cubes += d * d * d;
+    }
+    if squares.is_nan() || cubes.is_nan() {
+        return None;
+    }
+    // Every jackknife statistic the same: there's nothing to accelerate
+    if squares == 0.0 {
+        return Some(0.0);
+    }
+    Some(cubes / (6.0 * squares.powf(1.5)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mean::mean;
+    use super::super::quantile::median;
+    use super::*;
+
+    fn mean_of(values: &[f64]) -> f64 {
+        mean(values).unwrap()
+    }
+
+    #[test]
+    fn same_seed_same_numbers() {
+        assert_eq!(random_vector(&mut rng(Some(7)), 20, -5, 5), random_vector(&mut rng(Some(7)), 20, -5, 5));
+        assert_ne!(random_vector(&mut rng(Some(7)), 20, -5, 5), random_vector(&mut rng(Some(8)), 20, -5, 5));
+
+        let values = random_vector(&mut rng(Some(1)), 1000, -5, 5);
+        assert!(values.iter().all(|v| *v >= -5 && *v <= 5));
+        assert!(values.contains(&-5) && values.contains(&5));
+        assert_eq!(vec![i32::MAX; 3], random_vector(&mut rng(Some(1)), 3, i32::MAX, i32::MAX));
+
+        let values: Vec<f64> = (1..=30).map(|v| v as f64).collect();
+        let bootstrap = Bootstrap::new(500);
+        assert_eq!(
+            bootstrap.bca(&values, mean_of, &mut rng(Some(3))),
+            bootstrap.bca(&values, mean_of, &mut rng(Some(3)))
+        );
+    }
+
+    #[test]
+    fn mean_of_evenly_spread_values() {
+        // The mean of 1 to 100 has a standard error of about 2.9, so a 95%
+        // interval should reach about 1.96 * 2.9 = 5.7 either side of 50.5
+        let values: Vec<f64> = (1..=100).map(|v| v as f64).collect();
+        let bootstrap = Bootstrap::new(4000);
+
+        for interval in [
+            bootstrap.percentile(&values, mean_of, &mut rng(Some(42))).unwrap(),
+            bootstrap.bca(&values, mean_of, &mut rng(Some(42))).unwrap(),
+        ]
+        .iter()
+        {
+            assert_eq!(50.5, interval.estimate);
+            assert_eq!(0.95, interval.confidence);
+            assert!((interval.lower - 44.8).abs() < 0.6, "lower end {}", interval.lower);
+            assert!((interval.upper - 56.2).abs() < 0.6, "upper end {}", interval.upper);
+        }
+
+        let narrower = bootstrap.confidence(0.5).percentile(&values, mean_of, &mut rng(Some(42))).unwrap();
+        assert!(narrower.lower > 47.0 && narrower.upper < 54.0);
+    }
+
+    #[test]
+    fn symmetric_data_has_no_acceleration() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert!(acceleration(&values, &mean_of).unwrap().abs() < 1e-12);
+        assert_eq!(Some(0.0), acceleration(&[2.0, 2.0, 2.0], &mean_of));
+
+        // A long right tail speeds the mean's standard error up
+        let skewed = [1.0, 1.0, 2.0, 2.0, 3.0, 50.0];
+        assert!(acceleration(&skewed, &mean_of).unwrap() > 0.1);
+    }
+
+    #[test]
+    fn bca_follows_the_skew() {
+        // Mostly small values and a few huge ones, like request latencies.
+        // The mean's interval should reach further up than down, and BCa
+        // should push it further up than the percentile interval does.
+        let mut values: Vec<f64> = (0..40).map(|v| (v % 5 + 1) as f64).collect();
+        values.extend_from_slice(&[40.0, 60.0, 90.0]);
+        let bootstrap = Bootstrap::new(4000);
+        let percentile = bootstrap.percentile(&values, mean_of, &mut rng(Some(9))).unwrap();
+        let bca = bootstrap.bca(&values, mean_of, &mut rng(Some(9))).unwrap();
+
+        assert!(percentile.upper - percentile.estimate > percentile.estimate - percentile.lower);
+        assert!(bca.upper - bca.estimate > bca.estimate - bca.lower);
+        assert!(bca.lower > percentile.lower);
+        assert!(bca.upper > percentile.upper);
+    }
+
+    #[test]
+    fn any_statistic() {
+        let values = random_vector(&mut rng(Some(5)), 200, 0, 100);
+        let interval = Bootstrap::new(2000)
+            .percentile(&values, |v| median(v).unwrap(), &mut rng(Some(5)))
+            .unwrap();
+        assert_eq!(median(&values).unwrap(), interval.estimate);
+        assert!(interval.lower <= interval.estimate && interval.estimate <= interval.upper);
+        assert!(interval.upper - interval.lower < 30.0);
+    }
+
+    #[test]
+    fn nothing_to_bootstrap() {
+        let mut rng = rng(Some(1));
+        let bootstrap = Bootstrap::new(100);
+        assert_eq!(None, bootstrap.percentile(&[] as &[f64], mean_of, &mut rng));
+        assert_eq!(None, bootstrap.bca(&[1.0], mean_of, &mut rng));
+        assert_eq!(None, Bootstrap::new(0).percentile(&[1.0, 2.0], mean_of, &mut rng));
+        assert_eq!(None, bootstrap.confidence(1.0).percentile(&[1.0, 2.0], mean_of, &mut rng));
+        assert_eq!(None, bootstrap.percentile(&[1.0, 2.0], |_| f64::NAN, &mut rng));
+
+        // Every resample is the same as the values, so there's nothing to
+        // correct for and nowhere for the interval to go
+        let flat = bootstrap.bca(&[3.0, 3.0], mean_of, &mut rng).unwrap();
+        assert_eq!((3.0, 3.0), (flat.lower, flat.upper));
+    }
+}
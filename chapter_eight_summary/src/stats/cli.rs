@@ -0,0 +1,251 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+
+use super::histogram::{Bins, Histogram};
+use super::input::{read_csv_column, read_csv_pairs, read_numbers, Column, Numbers, Skipped};
+use super::paired::{covariance, linear_regression, pearson, spearman};
+use super::quantile::{five_number_summary, quantiles, QuantileMethod};
+use super::summary::Summary;
+use super::{calc_mode, Mode};
+
+// What `stats` was asked to read
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    // None, or "-", reads stdin
+    pub path: Option<String>,
+    // Read this CSV column instead of every number in the input
+    pub column: Option<Column>,
+    // Relate `column` to this one instead of summarising it
+    pub against: Option<Column>,
+    pub delimiter: char,
+}
+
+impl Options {
+    // `args` are the arguments after `stats`:
+    //     stats [FILE] [--column NAME|N [--against NAME|N]] [--delimiter C]
+    pub fn new(args: &[String]) -> Result<Options, &'static str> {
+        let mut path = None;
+        let mut column = None;
+        let mut against = None;
+        let mut delimiter = ',';
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--column" => match iter.next() {
+                    Some(name) => column = Some(name.parse()?),
+                    None => return Err("--column needs a column name or number"),
+                },
+                "--against" => match iter.next() {
+                    Some(name) => against = Some(name.parse()?),
+                    None => return Err("--against needs a column name or number"),
+                },
+                "--delimiter" => match iter.next().map(|d| d.as_str()) {
+                    Some("\\t") | Some("tab") => delimiter = '\t',
+                    Some(d) if d.chars().count() == 1 => delimiter = d.chars().next().unwrap(),
+                    _ => return Err("--delimiter needs a single character"),
+                },
+                _ if path.is_none() => path = Some(arg.clone()),
+                _ => return Err("stats reads only one file, use - for stdin"),
+            }
+        }
+
+        if against.is_some() && column.is_none() {
+            return Err("--against needs --column to say what to relate it to");
+        }
+        if path.as_deref() == Some("-") {
+            path = None;
+        }
+        Ok(Options { path, column, against, delimiter })
+    }
+}
+
+// Reads the numbers, reports the cells it had to skip on stderr and prints
+// every statistic we have for them
+pub fn run(options: &Options) -> Result<(), Box<dyn Error>> {
+    match &options.path {
+        Some(path) => run_on(BufReader::new(File::open(path)?), options),
+        None => run_on(io::stdin().lock(), options),
+    }
+}
+
+fn run_on<R: io::BufRead>(reader: R, options: &Options) -> Result<(), Box<dyn Error>> {
+    let stdout = io::stdout();
+
+    // With --against, relate the two columns instead
+    if let (Some(column), Some(against)) = (&options.column, &options.against) {
+        let pairs = read_csv_pairs(reader, against, column, options.delimiter)?;
+        report_skipped(&pairs.skipped);
+        if pairs.x.is_empty() {
+            return Err("found no pairs of numbers to relate".into());
+        }
+        report_pairs(&mut stdout.lock(), &pairs.x, &pairs.y)?;
+        return Ok(());
+    }
+
+    let numbers: Numbers = match &options.column {
+        Some(column) => read_csv_column(reader, column, options.delimiter)?,
+        None => read_numbers(reader)?,
+    };
+    report_skipped(&numbers.skipped);
+    if numbers.values.is_empty() {
+        return Err("found no numbers to summarise".into());
+    }
+    report(&mut stdout.lock(), &numbers.values)?;
+    Ok(())
+}
+
+fn report_skipped(skipped: &[Skipped]) {
+    for cell in skipped.iter() {
+        eprintln!("Skipped line {}: '{}' is not a number", cell.line, cell.text);
+    }
+    if !skipped.is_empty() {
+        eprintln!("Skipped {} value(s) in total", skipped.len());
+    }
+}
+
+// Writes the full summary of some numbers
+pub fn report<W: Write>(w: &mut W, values: &[f64]) -> io::Result<()> {
+    let summary: Summary<f64> = values.iter().cloned().collect();
+
+    writeln!(w, "Count:    {}", summary.count())?;
+    writeln!(w, "Sum:      {}", summary.sum())?;
+    writeln!(w, "Mean:     {}", show(summary.mean()))?;
+    writeln!(w, "Std dev:  {}", show(summary.std_dev()))?;
+    writeln!(w, "Variance: {}", show(summary.variance()))?;
+    writeln!(w, "Skewness: {}", show(summary.skewness()))?;
+    writeln!(w, "Kurtosis: {}", show(summary.kurtosis()))?;
+
+    if let Some(five) = five_number_summary(values) {
+        writeln!(w, "Min:      {}", five.min)?;
+        writeln!(w, "Q1:       {}", five.lower_quartile)?;
+        writeln!(w, "Median:   {}", five.median)?;
+        writeln!(w, "Q3:       {}", five.upper_quartile)?;
+        writeln!(w, "Max:      {}", five.max)?;
+        writeln!(w, "IQR:      {}", five.upper_quartile - five.lower_quartile)?;
+    }
+    if let Some(p) = quantiles(values, &[0.9, 0.95, 0.99], QuantileMethod::Linear) {
+        writeln!(w, "p90:      {}", p[0])?;
+        writeln!(w, "p95:      {}", p[1])?;
+        writeln!(w, "p99:      {}", p[2])?;
+    }
+
+    // Only whole numbers have a mode worth reporting: with decimals nearly
+    // every value shows up just once
+    if values.iter().all(|v| v.fract() == 0.0 && v.abs() < 9e15) {
+        let whole: Vec<i64> = values.iter().map(|v| *v as i64).collect();
+        match calc_mode(&whole) {
+            Mode::Unimodal(m, freq) => writeln!(w, "Mode:     {} ({} times)", m, freq)?,
+            Mode::Multimodal(modes, freq) => {
+                let modes: Vec<String> = modes.iter().map(|m| m.to_string()).collect();
+                writeln!(w, "Modes:    {} ({} times each)", modes.join(", "), freq)?;
+            },
+            Mode::Uniform(_) | Mode::None => writeln!(w, "Mode:     none")?,
+        };
+    }
+
+    if let Ok(histogram) = Histogram::new(values, Bins::FreedmanDiaconis) {
+        write!(w, "\n{}", histogram)?;
+    }
+    Ok(())
+}
+
+// Writes how y relates to x
+pub fn report_pairs<W: Write>(w: &mut W, x: &[f64], y: &[f64]) -> io::Result<()> {
+    writeln!(w, "Pairs:      {}", x.len().min(y.len()))?;
+    writeln!(w, "Covariance: {}", show(covariance(x, y)))?;
+    writeln!(w, "Pearson:    {}", show(pearson(x, y)))?;
+    writeln!(w, "Spearman:   {}", show(spearman(x, y)))?;
+
+    match linear_regression(x, y) {
+        Some(fit) => {
+            writeln!(w, "Fit:        y = {} * x + {}", fit.slope, fit.intercept)?;
+            writeln!(w, "R squared:  {}", fit.r_squared)?;
+            let worst = fit.residuals.iter().cloned().fold(0.0, |worst: f64, r| if r.abs() > worst.abs() { r } else { worst });
+            writeln!(w, "Largest residual: {}", worst)?;
+        },
+        None => writeln!(w, "Fit:        n/a")?,
+    };
+    Ok(())
+}
+
+fn show(value: Option<f64>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => String::from("n/a"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_options() {
+        let options = Options::new(&args(&["data.csv", "--column", "latency", "--delimiter", ";"])).unwrap();
+        assert_eq!(
+            Options {
+                path: Some(String::from("data.csv")),
+                column: Some(Column::Name(String::from("latency"))),
+                against: None,
+                delimiter: ';',
+            },
+            options
+        );
+
+        let options = Options::new(&args(&["--column", "3", "-"])).unwrap();
+        assert_eq!(None, options.path);
+        assert_eq!(Some(Column::Index(3)), options.column);
+
+        assert_eq!('\t', Options::new(&args(&["--delimiter", "tab"])).unwrap().delimiter);
+
+        let options = Options::new(&args(&["--column", "latency", "--against", "1"])).unwrap();
+        assert_eq!(Some(Column::Index(1)), options.against);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Options::new(&args(&["--column"])).is_err());
+        assert!(Options::new(&args(&["--delimiter", "ab"])).is_err());
+        assert!(Options::new(&args(&["a.txt", "b.txt"])).is_err());
+        assert!(Options::new(&args(&["--against", "load"])).is_err());
+    }
+
+    #[test]
+    fn full_report() {
+        let mut out = Vec::new();
+        report(&mut out, &[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("Count:    8\nSum:      40\nMean:     5\n"));
+        assert!(out.contains("Median:   4.5\n"));
+        assert!(out.contains("Mode:     4 (3 times)\n"));
+        assert!(out.contains("[  2, 3.4) | "));
+    }
+
+    #[test]
+    fn report_without_mode_for_decimals() {
+        let mut out = Vec::new();
+        report(&mut out, &[1.5]).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("Std dev:  n/a\n"));
+        assert!(!out.contains("Mode"));
+    }
+
+    #[test]
+    fn pairs_report() {
+        let mut out = Vec::new();
+        report_pairs(&mut out, &[1.0, 2.0, 3.0, 4.0], &[3.0, 5.0, 7.0, 10.0]).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("Pairs:      4\n"));
+        assert!(out.contains("Spearman:   1\n"));
+        assert!(out.contains("Fit:        y = 2.3 * x + 0.5\n"));
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use super::quantile;
+use super::summary::Sample;
+
+// How many times each distinct value shows up
+pub fn frequencies<T: Hash + Eq>(values: &[T]) -> HashMap<&T, usize> {
+    let mut table = HashMap::new();
+    for value in values.iter() {
+        *table.entry(value).or_insert(0) += 1;
+    }
+    table
+}
+
+// How to split the range of the data into bins
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bins {
+    // This many bins of the same width, from the smallest to the largest value
+    Count(usize),
+    // Bins of this width, lined up on multiples of it
+    Width(f64),
+    // log2(n) + 1 bins, which works well for small, roughly normal data
+    Sturges,
+    // Bins 2 * IQR / n^(1/3) wide, which copes better with outliers and
+    // long tails than Sturges
+    FreedmanDiaconis,
+    // Bins between these edges, which must be increasing. Values outside
+    // the first and last edge aren't counted in any bin.
+    Edges(Vec<f64>),
+}
+
+// More bins than this is never worth drawing, and one far-off outlier could
+// otherwise ask for billions of them
+const MAX_BINS: usize = 10_000;
+
+// Counts of values falling into consecutive bins. Every bin includes its
+// lower edge and excludes its upper edge, except the last, which includes
+// both so the largest value isn't left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    edges: Vec<f64>,
+    counts: Vec<u64>,
+    outside: u64,
+}
+
+impl Histogram {
+    // NaNs and infinities are left out. An error when the bins don't make
+    // sense or there would be more than MAX_BINS of them, or when there are
+    // no values to work out the bins from.
+    pub fn new<T: Sample>(values: &[T], bins: Bins) -> Result<Histogram, &'static str> {
+        let values: Vec<f64> = values.iter().map(|v| v.to_f64()).filter(|v| v.is_finite()).collect();
+
+        let edges = match bins {
+            Bins::Edges(edges) => {
+                if edges.len() < 2 {
+                    return Err("a histogram needs at least two bin edges");
+                }
+                if edges.iter().any(|edge| edge.is_nan()) || edges.windows(2).any(|pair| pair[0] >= pair[1]) {
+                    return Err("bin edges must be increasing");
+                }
+                edges
+            },
+            bins => {
+                let (min, max) = match min_max(&values) {
+                    Some(range) => range,
+                    None => return Err("can't pick bins for a histogram of no values"),
+                };
+                let count = match bins {
+                    Bins::Count(0) => return Err("a histogram needs at least one bin"),
+                    Bins::Count(count) if count > MAX_BINS => return Err("too many bins for a histogram"),
+                    Bins::Count(count) => count,
+                    Bins::Width(width) => {
+                        if !(width.is_finite() && width > 0.0) {
+                            return Err("bin width must be a positive number");
+                        }
+                        if (max / width).floor() - (min / width).floor() + 1.0 > MAX_BINS as f64 {
+                            return Err("bin width is too small for the range of the values");
+                        }
+                        return Ok(Histogram::from_edges(&values, aligned_edges(min, max, width)));
+                    },
+                    Bins::Sturges => sturges(values.len()),
+                    Bins::FreedmanDiaconis => {
+                        let iqr = quantile::iqr(&values).unwrap();
+                        let width = 2.0 * iqr / (values.len() as f64).cbrt();
+                        let count = ((max - min) / width).ceil().max(1.0);
+                        // Over half the values are the same, so there is no
+                        // spread to work from; or an outlier far from the
+                        // rest asks for more bins than there are values,
+                        // nearly all of them empty
+                        let limit = (2 * values.len()).min(MAX_BINS);
+                        if width > 0.0 && max > min && count <= limit as f64 {
+                            count as usize
+                        } else {
+                            sturges(values.len())
+                        }
+                    },
+                    Bins::Edges(_) => unreachable!(),
+                };
+                even_edges(min, max, count)
+            },
+        };
+
+        Ok(Histogram::from_edges(&values, edges))
+    }
+
+    fn from_edges(values: &[f64], edges: Vec<f64>) -> Histogram {
+        let mut histogram = Histogram {
+            counts: vec![0; edges.len() - 1],
+            edges,
+            outside: 0,
+        };
+        for value in values.iter() {
+            match histogram.bin_of(*value) {
+                Some(bin) => histogram.counts[bin] += 1,
+                None => histogram.outside += 1,
+            }
+        }
+        histogram
+    }
+
+    // Which bin a value falls into, if any
+    fn bin_of(&self, value: f64) -> Option<usize> {
+        let last = *self.edges.last().unwrap();
+        if value < self.edges[0] || value > last {
+            return None;
+        }
+        if value == last {
+            return Some(self.counts.len() - 1);
+        }
+        // The number of edges at or below the value, minus one
+        let above = self.edges.partition_point(|edge| *edge <= value);
+        above.checked_sub(1)
+    }
+
+    // The edges between bins: there is one more edge than there are bins
+    pub fn edges(&self) -> &[f64] {
+        &self.edges
+    }
+
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    // How many values were counted in a bin
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    // How many values were outside every bin (only possible with Bins::Edges)
+    pub fn outside(&self) -> u64 {
+        self.outside
+    }
+
+    // Each bin's count divided by the total and by the bin's width, so the
+    // bars' areas add up to 1 even when the bins aren't all the same width
+    pub fn densities(&self) -> Vec<f64> {
+        let total = self.total() as f64;
+        self.counts
+            .iter()
+            .zip(self.edges.windows(2))
+            .map(|(count, edge)| if total == 0.0 { 0.0 } else { *count as f64 / (total * (edge[1] - edge[0])) })
+            .collect()
+    }
+
+    // Draws the histogram as a bar chart, one bin per line, with the longest
+    // bar `bar_width` characters long:
+    //     [ 0, 10) | ######       3
+    //     [10, 20] | ############ 6
+    pub fn render(&self, bar_width: usize) -> String {
+        let labels: Vec<(String, String)> = self.edges.windows(2).map(|edge| (label(edge[0]), label(edge[1]))).collect();
+        let low_width = labels.iter().map(|l| l.0.len()).max().unwrap_or(0);
+        let high_width = labels.iter().map(|l| l.1.len()).max().unwrap_or(0);
+        let most = self.counts.iter().cloned().max().unwrap_or(0);
+
+        let mut chart = String::new();
+        for (bin, ((low, high), count)) in labels.iter().zip(self.counts.iter()).enumerate() {
+            // Round the bar to the nearest character, but never hide a bin
+            // that has something in it
+            let mut bar = if most == 0 { 0 } else { (*count as f64 * bar_width as f64 / most as f64).round() as usize };
+            if *count > 0 && bar == 0 {
+                bar = 1;
+            }
+            let close = if bin + 1 == self.counts.len() { ']' } else { ')' };
+            chart.push_str(&format!(
+                "[{:>lw$}, {:>hw$}{} | {:<bw$} {}\n",
+                low, high, close, "#".repeat(bar), count,
+                lw = low_width, hw = high_width, bw = bar_width,
+            ));
+        }
+        chart
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(40))
+    }
+}
+
+fn min_max(values: &[f64]) -> Option<(f64, f64)> {
+    let first = *values.first()?;
+    Some(values.iter().fold((first, first), |(min, max), v| (min.min(*v), max.max(*v))))
+}
+
+fn sturges(n: usize) -> usize {
+    (n as f64).log2().ceil() as usize + 1
+}
+
+// `count` bins of the same width from min to max. If every value is the
+// same there is no width to split, so use one bin around that value.
+fn even_edges(min: f64, max: f64, count: usize) -> Vec<f64> {
+    if min == max {
+        return vec![min - 0.5, max + 0.5];
+    }
+    let width = (max - min) / count as f64;
+    let mut edges: Vec<f64> = (0..count).map(|i| min + width * i as f64).collect();
+    // Exactly max, rather than whatever rounding makes of min + width * count
+    edges.push(max);
+    edges
+}
+
+// Bins of `width` starting at the multiple of width at or below min, up to
+// the first multiple above max
+fn aligned_edges(min: f64, max: f64, width: f64) -> Vec<f64> {
+    let first = (min / width).floor();
+    let last = (max / width).floor() + 1.0;
+    (first as i64..=last as i64).map(|i| i as f64 * width).collect()
+}
+
+// Edges printed with at most two decimal places, and none when they're
+// whole numbers
+fn label(x: f64) -> String {
+    let text = format!("{:.2}", x);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        String::from("0")
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequency_table() {
+        let table = frequencies(&["a", "b", "a", "c", "a"]);
+        assert_eq!(3, table[&"a"]);
+        assert_eq!(1, table[&"b"]);
+        assert_eq!(3, table.len());
+    }
+
+    #[test]
+    fn fixed_count() {
+        let histogram = Histogram::new(&[1, 2, 2, 3, 3, 3, 4, 4, 4, 4], Bins::Count(3)).unwrap();
+        assert_eq!(&[1.0, 2.0, 3.0, 4.0], histogram.edges());
+        // The largest value goes in the last bin
+        assert_eq!(&[1, 2, 7], histogram.counts());
+        assert_eq!(10, histogram.total());
+    }
+
+    #[test]
+    fn fixed_width() {
+        let histogram = Histogram::new(&[-3.5, 0.0, 9.99, 10.0, 24.0], Bins::Width(10.0)).unwrap();
+        assert_eq!(&[-10.0, 0.0, 10.0, 20.0, 30.0], histogram.edges());
+        assert_eq!(&[1, 2, 1, 1], histogram.counts());
+        assert!(Histogram::new(&[1], Bins::Width(0.0)).is_err());
+    }
+
+    #[test]
+    fn sturges_bins() {
+        let values: Vec<u32> = (0..100).collect();
+        let histogram = Histogram::new(&values, Bins::Sturges).unwrap();
+        // log2(100) = 6.6, so 7 + 1 bins
+        assert_eq!(8, histogram.counts().len());
+        assert_eq!(100, histogram.total());
+    }
+
+    #[test]
+    fn freedman_diaconis_bins() {
+        let values: Vec<u32> = (0..=1000).collect();
+        let histogram = Histogram::new(&values, Bins::FreedmanDiaconis).unwrap();
+        // IQR is 500, so bins are 2 * 500 / 10.003 = 99.97 wide, and it
+        // takes 11 of them to cover 0 to 1000
+        assert_eq!(11, histogram.counts().len());
+
+        // No spread in the middle half, so fall back to Sturges
+        let histogram = Histogram::new(&[5, 5, 5, 5, 5, 1, 9], Bins::FreedmanDiaconis).unwrap();
+        assert_eq!(4, histogram.counts().len());
+    }
+
+    #[test]
+    fn extreme_outlier() {
+        // 1 to 1000 would take bins about 100 wide, so reaching 1e13 would
+        // take 1e11 of them: Sturges instead
+        let mut values: Vec<f64> = (1..=1000).map(f64::from).collect();
+        values.push(1e13);
+        let histogram = Histogram::new(&values, Bins::FreedmanDiaconis).unwrap();
+        assert_eq!(11, histogram.counts().len());
+        assert_eq!(1001, histogram.total());
+
+        assert!(Histogram::new(&[0.0, 1e13], Bins::Width(1.0)).is_err());
+        assert!(Histogram::new(&[0.0, 1.0], Bins::Count(MAX_BINS + 1)).is_err());
+    }
+
+    #[test]
+    fn infinities_are_left_out() {
+        let histogram = Histogram::new(&[1.0, 2.0, 3.0, f64::INFINITY, f64::NEG_INFINITY], Bins::Sturges).unwrap();
+        assert_eq!(3, histogram.total());
+        assert_eq!(0, histogram.outside());
+        assert_eq!(&[1.0, 3.0], &[histogram.edges()[0], *histogram.edges().last().unwrap()]);
+        assert!(Histogram::new(&[f64::INFINITY], Bins::FreedmanDiaconis).is_err());
+    }
+
+    #[test]
+    fn custom_edges() {
+        let histogram = Histogram::new(&[0, 1, 5, 10, 99, 100, 101], Bins::Edges(vec![1.0, 10.0, 100.0])).unwrap();
+        assert_eq!(&[2, 3], histogram.counts());
+        assert_eq!(2, histogram.outside());
+        assert!(Histogram::new(&[0], Bins::Edges(vec![1.0, 1.0])).is_err());
+    }
+
+    #[test]
+    fn densities_add_up_to_one() {
+        let histogram = Histogram::new(&[1, 2, 3, 50], Bins::Edges(vec![0.0, 10.0, 100.0])).unwrap();
+        let densities = histogram.densities();
+        assert_eq!(vec![3.0 / 40.0, 1.0 / 360.0], densities);
+        let area: f64 = densities.iter().zip(histogram.edges().windows(2)).map(|(d, e)| d * (e[1] - e[0])).sum();
+        assert!((area - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn same_values() {
+        let histogram = Histogram::new(&[7, 7, 7], Bins::Sturges).unwrap();
+        assert_eq!(&[6.5, 7.5], histogram.edges());
+        assert_eq!(&[3], histogram.counts());
+    }
+
+    #[test]
+    fn no_values() {
+        assert!(Histogram::new::<i32>(&[], Bins::Sturges).is_err());
+        let histogram = Histogram::new::<i32>(&[], Bins::Edges(vec![0.0, 1.0])).unwrap();
+        assert_eq!(vec![0.0], histogram.densities());
+    }
+
+    #[test]
+    fn render_bars() {
+        let histogram = Histogram::new(&[0, 5, 12, 15, 15, 18, 100], Bins::Edges(vec![0.0, 10.0, 20.0, 1000.0])).unwrap();
+        assert_eq!(
+            "[ 0,   10) | ##   2\n\
+             [10,   20) | #### 4\n\
+             [20, 1000] | #    1\n",
+            histogram.render(4)
+        );
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!("3", label(3.0));
+        assert_eq!("3.33", label(10.0 / 3.0));
+        assert_eq!("-1.5", label(-1.5));
+        assert_eq!("0", label(-0.001));
+    }
+}
@@ -0,0 +1,240 @@
+use super::paired::ranks;
+use super::special::{chi_square_upper_tail, normal_upper_tail, t_two_tailed};
+use super::summary::{Sample, Summary};
+
+// What a hypothesis test found. A small p-value (say below 0.05) means data
+// this far from what the null hypothesis predicts would rarely happen by
+// chance, so the null hypothesis is probably wrong. Every p-value here is
+// two-sided: it doesn't care which way the difference goes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestResult {
+    pub statistic: f64,
+    // None for tests without any, like Mann-Whitney
+    pub degrees_of_freedom: Option<f64>,
+    pub p_value: f64,
+}
+
+// Student's t-test of whether the mean of the values could be `mean`. None
+// with fewer than two values, or when they're all the same.
+pub fn one_sample_t_test<T: Sample>(values: &[T], mean: f64) -> Option<TestResult> {
+    let summary: Summary<T> = values.iter().cloned().collect();
+    let std_dev = summary.std_dev()?;
+    if std_dev == 0.0 {
+        return None;
+    }
+
+    let n = summary.count() as f64;
+    let t = (summary.mean()? - mean) / (std_dev / n.sqrt());
+    let df = n - 1.0;
+    Some(TestResult {
+        statistic: t,
+        degrees_of_freedom: Some(df),
+        p_value: t_two_tailed(t, df),
+    })
+}
+
+// Welch's t-test of whether two samples could have the same mean. Unlike
+// Student's two-sample test it doesn't assume they have the same variance,
+// which benchmark runs on different code rarely do. None when either sample
+// has fewer than two values, or neither varies at all.
+pub fn welch_t_test<T: Sample>(a: &[T], b: &[T]) -> Option<TestResult> {
+    let a: Summary<T> = a.iter().cloned().collect();
+    let b: Summary<T> = b.iter().cloned().collect();
+    let (na, nb) = (a.count() as f64, b.count() as f64);
+
+    // The squared standard errors of the two means
+    let sa = a.variance()? / na;
+    let sb = b.variance()? / nb;
+    if sa + sb == 0.0 {
+        return None;
+    }
+
+    let t = (a.mean()? - b.mean()?) / (sa + sb).sqrt();
+    // The Welch-Satterthwaite estimate, usually not a whole number
+    let df = (sa + sb) * (sa + sb) / (sa * sa / (na - 1.0) + sb * sb / (nb - 1.0));
+    Some(TestResult {
+        statistic: t,
+        degrees_of_freedom: Some(df),
+        p_value: t_two_tailed(t, df),
+    })
+}
+
+// Pearson's chi-square test of whether the observed counts could come from
+// the expected distribution. `expected` can be counts or proportions: it's
+// scaled to the same total as `observed`. None when the lengths differ,
+// there are fewer than two categories, an expected value isn't positive,
+// or nothing was observed at all.
+//
+// The test is only trustworthy when every expected count is about 5 or more.
+pub fn chi_square_test(observed: &[u64], expected: &[f64]) -> Option<TestResult> {
+    if observed.len() != expected.len() || observed.len() < 2 {
+        return None;
+    }
+    if expected.iter().any(|e| e.is_nan() || *e <= 0.0 || e.is_infinite()) {
+        return None;
+    }
+
+    let total_observed: u64 = observed.iter().sum();
+    if total_observed == 0 {
+        return None;
+    }
+    let total_expected: f64 = expected.iter().sum();
+    let scale = total_observed as f64 / total_expected;
+
+    let statistic: f64 = observed
+        .iter()
+        .zip(expected.iter())
+        .map(|(o, e)| {
+            let e = e * scale;
+            (*o as f64 - e) * (*o as f64 - e) / e
+        })
+        .sum();
+    let df = (observed.len() - 1) as f64;
+    Some(TestResult {
+        statistic,
+        degrees_of_freedom: Some(df),
+        p_value: chi_square_upper_tail(statistic, df),
+    })
+}
+
+// The Mann-Whitney U test (also called the Wilcoxon rank-sum test) of
+// whether values from one sample tend to be larger than from the other. It
+// only looks at the order of the values, so unlike the t-test it doesn't
+// need them to be normally distributed and outliers can't swamp it.
+//
+// The statistic is U for `a`: how many of the (a, b) pairs have the a value
+// larger, counting ties as half. The p-value uses the normal approximation
+// with corrections for ties and continuity, which is good once both samples
+// have more than a handful of values. None if either sample is empty, has
+// a NaN (which can't be put in order) or every value is the same.
+pub fn mann_whitney_u<T: Sample>(a: &[T], b: &[T]) -> Option<TestResult> {
+    if a.is_empty() || b.is_empty() || a.iter().chain(b.iter()).any(|v| v.to_f64().is_nan()) {
+        return None;
+    }
+
+    let mut all: Vec<f64> = a.iter().chain(b.iter()).map(|v| v.to_f64()).collect();
+    let all_ranks = ranks(&all);
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let n = na + nb;
+
+    let rank_sum: f64 = all_ranks[..a.len()].iter().sum();
+    let u = rank_sum - na * (na + 1.0) / 2.0;
+
+    // Every group of t tied values makes the ranks vary a bit less
+    all.sort_by(|x, y| x.total_cmp(y));
+    let mut ties = 0.0;
+    let mut start = 0;
+    while start < all.len() {
+        let mut end = start;
+        while end + 1 < all.len() && all[end + 1] == all[start] {
+            end += 1;
+        }
+        let t = (end - start + 1) as f64;
+        ties += t * t * t - t;
+        start = end + 1;
+    }
+
+    let mean = na * nb / 2.0;
+    let variance = na * nb / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return None;
+    }
+    let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+
+    Some(TestResult {
+        statistic: u,
+        degrees_of_freedom: None,
+        p_value: (2.0 * normal_upper_tail(z)).min(1.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: [f64; 10] = [5.1, 4.9, 5.6, 5.8, 6.0, 5.3, 5.9, 6.1, 5.7, 5.5];
+    const B: [f64; 8] = [6.2, 6.8, 5.9, 7.1, 6.6, 6.9, 7.4, 6.0];
+
+    // Reference values are from the same formulas in mpmath at 30 digits
+    fn assert_close(expected: f64, actual: f64) {
+        assert!((expected - actual).abs() <= 1e-10 * expected.abs().max(1.0), "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn one_sample() {
+        let result = one_sample_t_test(&A, 5.0).unwrap();
+        assert_close(4.749_218_419_272_005, result.statistic);
+        assert_eq!(Some(9.0), result.degrees_of_freedom);
+        assert_close(0.001_045_335_588_908_698_6, result.p_value);
+
+        // Testing against the sample's own mean finds nothing
+        let mean = A.iter().sum::<f64>() / A.len() as f64;
+        assert_close(1.0, one_sample_t_test(&A, mean).unwrap().p_value);
+    }
+
+    #[test]
+    fn welch() {
+        let result = welch_t_test(&A, &B).unwrap();
+        assert_close(-4.498_646_441_705_262, result.statistic);
+        assert_close(12.473_791_758_149_973, result.degrees_of_freedom.unwrap());
+        assert_close(0.000_662_322_550_816_148, result.p_value);
+
+        // Swapping the samples only flips the sign
+        let swapped = welch_t_test(&B, &A).unwrap();
+        assert_close(-result.statistic, swapped.statistic);
+        assert_close(result.p_value, swapped.p_value);
+    }
+
+    #[test]
+    fn t_tests_need_spread() {
+        assert_eq!(None, one_sample_t_test(&[3.0], 1.0));
+        assert_eq!(None, one_sample_t_test(&[3, 3, 3], 1.0));
+        assert_eq!(None, welch_t_test(&[1, 1], &[2, 2]));
+        assert_eq!(None, welch_t_test(&[1, 2, 3], &[2]));
+    }
+
+    #[test]
+    fn fair_die() {
+        let rolls = [16, 18, 16, 14, 12, 24];
+        let result = chi_square_test(&rolls, &[1.0; 6]).unwrap();
+        assert_close(5.12, result.statistic);
+        assert_eq!(Some(5.0), result.degrees_of_freedom);
+        assert_close(0.401_411_593_246_095_4, result.p_value);
+    }
+
+    #[test]
+    fn chi_square_with_proportions() {
+        let result = chi_square_test(&[50, 30, 20], &[0.5, 0.25, 0.25]).unwrap();
+        assert_close(2.0, result.statistic);
+        assert_close(0.367_879_441_171_442_33, result.p_value);
+
+        assert_eq!(None, chi_square_test(&[1, 2], &[1.0]));
+        assert_eq!(None, chi_square_test(&[1, 2], &[1.0, 0.0]));
+        assert_eq!(None, chi_square_test(&[0, 0, 0], &[1.0; 3]));
+    }
+
+    #[test]
+    fn mann_whitney() {
+        let a = [1.1, 2.3, 3.3, 4.0, 4.0, 5.2];
+        let b = [3.9, 4.0, 5.5, 6.1, 7.0, 8.4, 9.2];
+        let result = mann_whitney_u(&a, &b).unwrap();
+        assert_eq!(5.0, result.statistic);
+        assert_eq!(None, result.degrees_of_freedom);
+        assert_close(0.025_977_141_744_859_653, result.p_value);
+
+        // U for b is the other n_a * n_b - U pairs
+        assert_eq!(37.0, mann_whitney_u(&b, &a).unwrap().statistic);
+    }
+
+    #[test]
+    fn mann_whitney_without_difference() {
+        let result = mann_whitney_u(&[1, 2, 3, 4], &[1, 2, 3, 4]).unwrap();
+        assert_eq!(8.0, result.statistic);
+        assert_eq!(1.0, result.p_value);
+
+        assert_eq!(None, mann_whitney_u::<i32>(&[], &[1]));
+        assert_eq!(None, mann_whitney_u(&[2, 2], &[2, 2]));
+        assert_eq!(None, mann_whitney_u(&[1.0, f64::NAN, 3.0], &[4.0, 5.0, 6.0]));
+        assert_eq!(None, mann_whitney_u(&[1.0, 2.0, 3.0], &[f64::NAN]));
+    }
+}
@@ -0,0 +1,283 @@
+use std::error::Error;
+use std::io::BufRead;
+use std::str::FromStr;
+
+// Which column of a CSV file to read the numbers from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    // The column with this header
+    Name(String),
+    // The n-th column, counting from 1 like `cut -f` does
+    Index(usize),
+}
+
+impl FromStr for Column {
+    type Err = &'static str;
+
+    // A number picks a column by position, anything else by header
+    fn from_str(s: &str) -> Result<Column, &'static str> {
+        match s.parse::<usize>() {
+            Ok(0) => Err("columns are counted from 1"),
+            Ok(n) => Ok(Column::Index(n)),
+            Err(_) if s.is_empty() => Err("column name can't be empty"),
+            Err(_) => Ok(Column::Name(s.to_string())),
+        }
+    }
+}
+
+// Something that should have been a number but wasn't
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    // Counting from 1
+    pub line: usize,
+    pub text: String,
+}
+
+// The numbers read from some input, and the cells that couldn't be read
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Numbers {
+    pub values: Vec<f64>,
+    pub skipped: Vec<Skipped>,
+}
+
+impl Numbers {
+    fn push(&mut self, line: usize, text: &str) {
+        match parse_cell(text) {
+            Some(value) => self.values.push(value),
+            None => self.skipped.push(Skipped { line, text: text.to_string() }),
+        }
+    }
+}
+
+// Reads numbers separated by whitespace or commas, any number per line.
+// Blank lines are fine; anything that isn't a number is skipped.
+pub fn read_numbers<R: BufRead>(reader: R) -> Result<Numbers, Box<dyn Error>> {
+    let mut numbers = Numbers::default();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        for text in line.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+            numbers.push(i + 1, text);
+        }
+    }
+    Ok(numbers)
+}
+
+// Reads one column of a CSV file. Picking the column by name needs a
+// header row. Picking it by index doesn't, but if the first row's cell
+// isn't a number it's taken to be a header instead of a skipped cell.
+// Rows too short to have the column are skipped as well.
+pub fn read_csv_column<R: BufRead>(reader: R, column: &Column, delimiter: char) -> Result<Numbers, Box<dyn Error>> {
+    let (rows, skipped) = read_csv_rows(reader, &[column], delimiter)?;
+    Ok(Numbers {
+        values: rows.into_iter().map(|row| row[0]).collect(),
+        skipped,
+    })
+}
+
+// Two columns of the same rows, to relate to each other
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Pairs {
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub skipped: Vec<Skipped>,
+}
+
+// Reads two columns of a CSV file, the same way as read_csv_column(). A row
+// is only kept when both of its cells are numbers, so x and y stay paired.
+pub fn read_csv_pairs<R: BufRead>(reader: R, x: &Column, y: &Column, delimiter: char) -> Result<Pairs, Box<dyn Error>> {
+    let (rows, skipped) = read_csv_rows(reader, &[x, y], delimiter)?;
+    Ok(Pairs {
+        x: rows.iter().map(|row| row[0]).collect(),
+        y: rows.iter().map(|row| row[1]).collect(),
+        skipped,
+    })
+}
+
+// The numbers in the given columns of each row that has a number in all of
+// them, and the cells (or whole rows) that were skipped
+type Rows = (Vec<Vec<f64>>, Vec<Skipped>);
+
+fn read_csv_rows<R: BufRead>(reader: R, columns: &[&Column], delimiter: char) -> Result<Rows, Box<dyn Error>> {
+    let mut rows = Vec::new();
+    let mut skipped = Vec::new();
+    let mut lines = reader.lines().enumerate();
+
+    // A column picked by name means the first line has to be a header
+    let mut header = None;
+    if columns.iter().any(|column| matches!(column, Column::Name(_))) {
+        header = match lines.next() {
+            Some((_, line)) => Some(split_row(&line?, delimiter)),
+            None => return Err("the CSV file is empty, so it has no header".into()),
+        };
+    }
+
+    let mut indexes = Vec::new();
+    for column in columns.iter() {
+        indexes.push(match column {
+            Column::Index(n) => n - 1,
+            Column::Name(name) => {
+                let header = header.as_ref().unwrap();
+                match header.iter().position(|cell| cell.trim() == name) {
+                    Some(index) => index,
+                    None => return Err(format!("there is no column called '{}'", name).into()),
+                }
+            },
+        });
+    }
+
+    for (i, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = split_row(&line, delimiter);
+        let cells: Option<Vec<&String>> = indexes.iter().map(|index| row.get(*index)).collect();
+        let cells = match cells {
+            Some(cells) => cells,
+            None => {
+                skipped.push(Skipped { line: i + 1, text: line.clone() });
+                continue;
+            },
+        };
+
+        let values: Vec<Option<f64>> = cells.iter().map(|cell| parse_cell(cell)).collect();
+        if values.iter().all(|value| value.is_some()) {
+            rows.push(values.into_iter().map(|value| value.unwrap()).collect());
+        } else if i == 0 && header.is_none() {
+            // Not numbers on the first line: a header nobody asked us to use
+        } else {
+            for (cell, value) in cells.iter().zip(values.iter()) {
+                if value.is_none() {
+                    skipped.push(Skipped { line: i + 1, text: cell.to_string() });
+                }
+            }
+        }
+    }
+
+    Ok((rows, skipped))
+}
+
+// NaN, "inf" and numbers too big for an f64 (like 1e999) parse, but none
+// of them is a measurement anything can be worked out from
+fn parse_cell(text: &str) -> Option<f64> {
+    match text.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() => Some(value),
+        _ => None,
+    }
+}
+
+// Splits a CSV row into cells. Cells can be wrapped in double quotes to
+// hold the delimiter, with "" for a quote inside one. A quoted cell can't
+// go over more than one line.
+fn split_row(line: &str, delimiter: char) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    cell.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            } else {
+                cell.push(c);
+            }
+        } else if c == '"' {
+            quoted = true;
+        } else if c == delimiter {
+            cells.push(cell);
+            cell = String::new();
+        } else {
+            cell.push(c);
+        }
+    }
+    cells.push(cell);
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_column() {
+        assert_eq!(Ok(Column::Index(2)), "2".parse());
+        assert_eq!(Ok(Column::Name(String::from("latency"))), "latency".parse());
+        assert!("0".parse::<Column>().is_err());
+        assert!("".parse::<Column>().is_err());
+    }
+
+    #[test]
+    fn numbers_and_bad_cells() {
+        let input = "1 2.5\n\n-3e2, oops ,4\nNaN\ninf -infinity 1e999\n";
+        let numbers = read_numbers(input.as_bytes()).unwrap();
+        assert_eq!(vec![1.0, 2.5, -300.0, 4.0], numbers.values);
+        assert_eq!(
+            vec![
+                Skipped { line: 3, text: String::from("oops") },
+                Skipped { line: 4, text: String::from("NaN") },
+                Skipped { line: 5, text: String::from("inf") },
+                Skipped { line: 5, text: String::from("-infinity") },
+                Skipped { line: 5, text: String::from("1e999") },
+            ],
+            numbers.skipped
+        );
+    }
+
+    #[test]
+    fn csv_column_by_name() {
+        let input = "host,\"latency, ms\",status\nweb1,12,200\nweb2,,500\nweb3,\"7\",200\nweb4\n";
+        let column = Column::Name(String::from("latency, ms"));
+        let numbers = read_csv_column(input.as_bytes(), &column, ',').unwrap();
+        assert_eq!(vec![12.0, 7.0], numbers.values);
+        assert_eq!(
+            vec![
+                Skipped { line: 3, text: String::from("") },
+                Skipped { line: 5, text: String::from("web4") },
+            ],
+            numbers.skipped
+        );
+
+        let column = Column::Name(String::from("size"));
+        assert!(read_csv_column(input.as_bytes(), &column, ',').is_err());
+    }
+
+    #[test]
+    fn csv_column_by_index() {
+        let with_header = "a;b\r\n1;2\r\n3;x\r\n";
+        let numbers = read_csv_column(with_header.as_bytes(), &Column::Index(2), ';').unwrap();
+        assert_eq!(vec![2.0], numbers.values);
+        assert_eq!(vec![Skipped { line: 3, text: String::from("x") }], numbers.skipped);
+
+        let without_header = "1;2\n3;4\n-inf;5\n";
+        let numbers = read_csv_column(without_header.as_bytes(), &Column::Index(1), ';').unwrap();
+        assert_eq!(vec![1.0, 3.0], numbers.values);
+        assert_eq!(vec![Skipped { line: 3, text: String::from("-inf") }], numbers.skipped);
+    }
+
+    #[test]
+    fn csv_pairs() {
+        let input = "load,host,latency\n0.5,a,12\n0.7,b,oops\nhigh,c,\n0.9,d,20\n";
+        let pairs = read_csv_pairs(input.as_bytes(), &Column::Name(String::from("load")), &Column::Index(3), ',').unwrap();
+        assert_eq!(vec![0.5, 0.9], pairs.x);
+        assert_eq!(vec![12.0, 20.0], pairs.y);
+        assert_eq!(
+            vec![
+                Skipped { line: 3, text: String::from("oops") },
+                Skipped { line: 4, text: String::from("high") },
+                Skipped { line: 4, text: String::from("") },
+            ],
+            pairs.skipped
+        );
+    }
+
+    #[test]
+    fn quoted_cells() {
+        assert_eq!(vec!["a,b", "say \"hi\"", ""], split_row("\"a,b\",\"say \"\"hi\"\"\",", ','));
+    }
+}
@@ -0,0 +1,152 @@
+use super::summary::Sample;
+
+// A running f64 total that doesn't lose the small values when it gets
+// big. Plain `+=` rounds away the low-order bits of every value added to a
+// large total; the Kahan-Babuska (Neumaier) trick keeps those bits in
+// `compensation` and adds them back at the end.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    pub fn new() -> CompensatedSum {
+        CompensatedSum { sum: 0.0, compensation: 0.0 }
+    }
+
+    pub fn add(&mut self, x: f64) {
+        let total = self.sum + x;
+        // Once the total is infinite (or NaN) there are no low-order bits
+        // left to keep, and working them out would give inf - inf = NaN
+        if !total.is_finite() {
+            self.sum = total;
+            return;
+        }
+        if self.sum.abs() >= x.abs() {
+            self.compensation += (self.sum - total) + x;
+        } else {
+            self.compensation += (x - total) + self.sum;
+        }
+        self.sum = total;
+    }
+
+    // Adds another running total, keeping the bits it saved as well
+    pub fn merge(&mut self, other: &CompensatedSum) {
+        self.add(other.sum);
+        self.add(other.compensation);
+    }
+
+    pub fn value(&self) -> f64 {
+        if !self.sum.is_finite() {
+            return self.sum;
+        }
+        self.sum + self.compensation
+    }
+}
+
+// The mean of any kind of number. There is no mean of nothing, so an
+// empty slice gives None instead of NaN.
+pub fn mean<T: Sample>(values: &[T]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sum = CompensatedSum::new();
+    for value in values.iter() {
+        sum.add(value.to_f64());
+    }
+    Some(sum.value() / values.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+    use num_traits::ToPrimitive;
+    use proptest::prelude::*;
+
+    // The exact sum, worked out with fractions that never round
+    fn exact_sum(values: &[f64]) -> BigRational {
+        let mut sum = BigRational::from_integer(BigInt::from(0));
+        for value in values.iter() {
+            sum += BigRational::from_float(*value).unwrap();
+        }
+        sum
+    }
+
+    #[test]
+    fn empty_has_no_mean() {
+        assert_eq!(None, mean::<f64>(&[]));
+        assert_eq!(None, mean::<u8>(&[]));
+    }
+
+    #[test]
+    fn small_values_are_not_lost() {
+        // Adding 1.0 to 1e16 one at a time does nothing with plain `+=`
+        let mut values = vec![1e16];
+        values.extend(vec![1.0; 1000]);
+        values.push(-1e16);
+        assert_eq!(Some(1000.0 / 1002.0), mean(&values));
+    }
+
+    #[test]
+    fn infinite_values() {
+        assert_eq!(Some(f64::INFINITY), mean(&[1.0, f64::INFINITY, 2.0]));
+        assert_eq!(Some(f64::NEG_INFINITY), mean(&[f64::NEG_INFINITY, 1e300]));
+        // The total overflows, even though no value is infinite
+        assert_eq!(Some(f64::INFINITY), mean(&[f64::MAX, f64::MAX, 1.0]));
+        assert!(mean(&[f64::INFINITY, f64::NEG_INFINITY]).unwrap().is_nan());
+
+        let mut sum = CompensatedSum::new();
+        sum.add(f64::INFINITY);
+        let mut other = CompensatedSum::new();
+        other.add(0.1);
+        sum.merge(&other);
+        assert_eq!(f64::INFINITY, sum.value());
+    }
+
+    #[test]
+    fn integers_that_overflow_i32() {
+        assert_eq!(Some(f64::from(i32::MAX)), mean(&[i32::MAX, i32::MAX, i32::MAX]));
+        assert_eq!(Some(u64::MAX as f64), mean(&[u64::MAX, u64::MAX]));
+    }
+
+    // How far off a compensated total may be: a couple of roundings of the
+    // total itself, plus a term that only matters when huge values cancel out
+    fn tolerance(expected: f64, values: &[f64]) -> f64 {
+        let absolute_sum: f64 = values.iter().map(|x| x.abs()).sum();
+        2.0 * f64::EPSILON * expected.abs() + values.len() as f64 * f64::EPSILON * f64::EPSILON * absolute_sum
+    }
+
+    proptest! {
+        #[test]
+        fn matches_exact_mean(values in prop::collection::vec(-1e12f64..1e12, 1..200)) {
+            let sum = exact_sum(&values);
+            let expected = (&sum / BigInt::from(values.len())).to_f64().unwrap();
+            let actual = mean(&values).unwrap();
+            // Dividing by the count rounds once more
+            let allowed = tolerance(sum.to_f64().unwrap(), &values) / values.len() as f64 + f64::EPSILON * expected.abs();
+            prop_assert!((expected - actual).abs() <= allowed, "expected {}, got {}", expected, actual);
+        }
+
+        #[test]
+        fn merged_sums_match_exact_sum(values in prop::collection::vec(-1e12f64..1e12, 1..200), split in 0usize..200) {
+            let split = split.min(values.len());
+            let mut left = CompensatedSum::new();
+            let mut right = CompensatedSum::new();
+            for value in values[..split].iter() {
+                left.add(*value);
+            }
+            for value in values[split..].iter() {
+                right.add(*value);
+            }
+            left.merge(&right);
+
+            let expected = exact_sum(&values).to_f64().unwrap();
+            let allowed = tolerance(expected, &values);
+            prop_assert!((expected - left.value()).abs() <= allowed, "expected {}, got {}", expected, left.value());
+        }
+    }
+}
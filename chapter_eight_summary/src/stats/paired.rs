@@ -0,0 +1,225 @@
+use super::summary::Sample;
+
+// Statistics of two series measured together, like the latency and the
+// load of a server at the same moments. x[i] and y[i] belong together, so
+// every function here gives None when the slices aren't the same length.
+
+// The means of x and y and the sums of squared (and multiplied) distances
+// from them, worked out in one pass with Welford's updates like Summary
+struct CoMoments {
+    n: f64,
+    mean_x: f64,
+    mean_y: f64,
+    sxx: f64,
+    syy: f64,
+    sxy: f64,
+}
+
+impl CoMoments {
+    fn new<T: Sample>(x: &[T], y: &[T]) -> Option<CoMoments> {
+        if x.len() != y.len() || x.len() < 2 {
+            return None;
+        }
+
+        let mut m = CoMoments { n: 0.0, mean_x: 0.0, mean_y: 0.0, sxx: 0.0, syy: 0.0, sxy: 0.0 };
+        for (a, b) in x.iter().zip(y.iter()) {
+            let (a, b) = (a.to_f64(), b.to_f64());
+            m.n += 1.0;
+            let dx = a - m.mean_x;
+            let dy = b - m.mean_y;
+            m.mean_x += dx / m.n;
+            m.mean_y += dy / m.n;
+            // One distance from the old mean times one from the new one
+            m.sxx += dx * (a - m.mean_x);
+            m.syy += dy * (b - m.mean_y);
+            m.sxy += dx * (b - m.mean_y);
+        }
+        Some(m)
+    }
+}
+
+// Sample covariance, dividing by n - 1. Positive when y tends to be above
+// its mean whenever x is.
+pub fn covariance<T: Sample>(x: &[T], y: &[T]) -> Option<f64> {
+    let m = CoMoments::new(x, y)?;
+    Some(m.sxy / (m.n - 1.0))
+}
+
+// Pearson's r: how close the points are to a straight line, from -1 (a
+// line going down) through 0 (no linear relation) to 1 (a line going up).
+// None when either series doesn't vary, since then there's no line to fit.
+pub fn pearson<T: Sample>(x: &[T], y: &[T]) -> Option<f64> {
+    let m = CoMoments::new(x, y)?;
+    if m.sxx == 0.0 || m.syy == 0.0 {
+        return None;
+    }
+    // Rounding can take the result a hair past 1
+    Some((m.sxy / (m.sxx * m.syy).sqrt()).clamp(-1.0, 1.0))
+}
+
+// Spearman's rho: Pearson's r of the ranks instead of the values, so it
+// measures whether y goes up when x does, in a straight line or not, and a
+// single outlier can't drag it around as much. A NaN has no place in the
+// order, so there's nothing to rank and the result is None.
+pub fn spearman<T: Sample>(x: &[T], y: &[T]) -> Option<f64> {
+    if x.len() != y.len() || x.iter().chain(y.iter()).any(|v| v.to_f64().is_nan()) {
+        return None;
+    }
+    pearson(&ranks(x), &ranks(y))
+}
+
+// The rank of each value, from 1 for the smallest. Tied values all get the
+// average of the ranks they cover, so [10, 20, 20, 30] ranks as
+// [1, 2.5, 2.5, 4]. NaNs rank after everything else and never tie.
+pub fn ranks<T: Sample>(values: &[T]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].to_f64().total_cmp(&values[*b].to_f64()));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let value = values[order[start]].to_f64();
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]].to_f64() == value {
+            end += 1;
+        }
+        let rank = (start + end) as f64 / 2.0 + 1.0;
+        for i in order[start..=end].iter() {
+            ranks[*i] = rank;
+        }
+        start = end + 1;
+    }
+    ranks
+}
+
+// The straight line y = slope * x + intercept that fits the points best,
+// in the sense of the smallest sum of squared residuals
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    // How much of the variation in y the line explains, from 0 to 1
+    pub r_squared: f64,
+    // How far each point's y is above (or below) the line
+    pub residuals: Vec<f64>,
+}
+
+impl LinearFit {
+    pub fn predict(&self, x: f64) -> f64 {
+        self.slope * x + self.intercept
+    }
+}
+
+// Ordinary least squares. None when the slices don't pair up, there are
+// fewer than two points, or every x is the same (the line would be
+// vertical).
+pub fn linear_regression<T: Sample>(x: &[T], y: &[T]) -> Option<LinearFit> {
+    let m = CoMoments::new(x, y)?;
+    if m.sxx == 0.0 {
+        return None;
+    }
+
+    let slope = m.sxy / m.sxx;
+    let intercept = m.mean_y - slope * m.mean_x;
+    // Every y the same is fit perfectly by a flat line
+    let r_squared = if m.syy == 0.0 { 1.0 } else { (m.sxy * m.sxy / (m.sxx * m.syy)).min(1.0) };
+    let residuals = x
+        .iter()
+        .zip(y.iter())
+        .map(|(a, b)| b.to_f64() - (slope * a.to_f64() + intercept))
+        .collect();
+
+    Some(LinearFit { slope, intercept, r_squared, residuals })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Anscombe's quartet: four data sets with (nearly) the same means,
+    // variances, correlation and regression line, that look nothing alike
+    // when plotted
+    const X: [f64; 11] = [10.0, 8.0, 13.0, 9.0, 11.0, 14.0, 6.0, 4.0, 12.0, 7.0, 5.0];
+    const Y1: [f64; 11] = [8.04, 6.95, 7.58, 8.81, 8.33, 9.96, 7.24, 4.26, 10.84, 4.82, 5.68];
+    const Y2: [f64; 11] = [9.14, 8.14, 8.74, 8.77, 9.26, 8.10, 6.13, 3.10, 9.13, 7.26, 4.74];
+    const Y3: [f64; 11] = [7.46, 6.77, 12.74, 7.11, 7.81, 8.84, 6.08, 5.39, 8.15, 6.42, 5.73];
+    const X4: [f64; 11] = [8.0, 8.0, 8.0, 8.0, 8.0, 8.0, 8.0, 19.0, 8.0, 8.0, 8.0];
+    const Y4: [f64; 11] = [6.58, 5.76, 7.71, 8.84, 8.47, 7.04, 5.25, 12.50, 5.56, 7.91, 6.89];
+
+    fn assert_close(expected: f64, actual: f64) {
+        assert!((expected - actual).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn anscombes_quartet() {
+        // covariance, r, slope, intercept and Spearman's rho of each set
+        let quartet = [
+            (&X, &Y1, 5.501, 0.81642051634484, 0.5000909090909091, 3.0000909090909103, 0.8181818181818182),
+            (&X, &Y2, 5.5, 0.8162365060002428, 0.5, 3.000909090909091, 0.6909090909090909),
+            (&X, &Y3, 5.497, 0.8162867394895981, 0.49972727272727274, 3.0024545454545466, 0.990909090909091),
+            (&X4, &Y4, 5.499, 0.8165214368885028, 0.49990909090909086, 3.0017272727272726, 0.5),
+        ];
+
+        for (x, y, cov, r, slope, intercept, rho) in quartet.iter() {
+            assert_close(*cov, covariance(*x, *y).unwrap());
+            assert_close(*r, pearson(*x, *y).unwrap());
+            assert_close(*rho, spearman(*x, *y).unwrap());
+
+            let fit = linear_regression(*x, *y).unwrap();
+            assert_close(*slope, fit.slope);
+            assert_close(*intercept, fit.intercept);
+            assert_close(r * r, fit.r_squared);
+
+            // The residuals of a least-squares fit always add up to 0
+            assert_close(0.0, fit.residuals.iter().sum());
+            assert_close(y[0] - fit.predict(x[0]), fit.residuals[0]);
+        }
+    }
+
+    #[test]
+    fn perfect_lines() {
+        let x = [1, 2, 3, 4];
+        let y = [3, 5, 7, 9];
+        assert_close(1.0, pearson(&x, &y).unwrap());
+        let fit = linear_regression(&x, &y).unwrap();
+        assert_eq!((2.0, 1.0, 1.0), (fit.slope, fit.intercept, fit.r_squared));
+        assert_eq!(vec![0.0; 4], fit.residuals);
+
+        let down = [8, 6, 4, 2];
+        assert_close(-1.0, pearson(&x, &down).unwrap());
+    }
+
+    #[test]
+    fn spearman_ignores_the_shape() {
+        // y goes up with x, just not in a straight line
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = [1.0, 4.0, 9.0, 100.0, 1000.0];
+        assert_eq!(Some(1.0), spearman(&x, &y));
+        assert!(pearson(&x, &y).unwrap() < 0.9);
+    }
+
+    #[test]
+    fn tied_ranks() {
+        assert_eq!(vec![1.0, 2.5, 2.5, 4.0], ranks(&[10, 20, 20, 30]));
+        assert_eq!(vec![2.0, 2.0, 2.0], ranks(&[5, 5, 5]));
+        assert_eq!(vec![3.0, 1.0, 4.0, 2.0], ranks(&[f64::NAN, 1.0, f64::NAN, 2.0]));
+    }
+
+    #[test]
+    fn spearman_with_nan() {
+        assert_eq!(None, spearman(&[1.0, f64::NAN, 3.0], &[1.0, 2.0, 3.0]));
+        assert_eq!(None, spearman(&[1.0, 2.0, 3.0], &[f64::NAN; 3]));
+    }
+
+    #[test]
+    fn nothing_to_relate() {
+        assert_eq!(None, covariance(&[1, 2], &[1]));
+        assert_eq!(None, covariance(&[1], &[1]));
+        assert_eq!(None, pearson(&[1, 1, 1], &[1, 2, 3]));
+        assert_eq!(None, linear_regression(&[2, 2], &[1, 5]));
+
+        // A flat line fits points that don't vary perfectly
+        let fit = linear_regression(&[1, 2, 3], &[4, 4, 4]).unwrap();
+        assert_eq!((0.0, 4.0, 1.0), (fit.slope, fit.intercept, fit.r_squared));
+    }
+}
@@ -0,0 +1,356 @@
+use super::summary::Sample;
+
+// The nine ways of estimating a quantile from a sample described by
+// Hyndman and Fan ("Sample Quantiles in Statistical Packages", 1996). The
+// number in each comment is the type number from the paper, which is also
+// what R's `quantile(type = ...)` uses.
+//
+// The first three always give back one of the values (or the average of
+// two of them); the others draw straight lines between neighbouring values.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum QuantileMethod {
+    // 1: the smallest value with at least p of the data at or below it
+    InvertedCdf,
+    // 2: like InvertedCdf, but averages the two values at a tie
+    AveragedInvertedCdf,
+    // 3: the nearest value, picking the even one at a tie (SAS's default)
+    ClosestObservation,
+    // 4: linear interpolation of the empirical distribution function
+    InterpolatedInvertedCdf,
+    // 5: the midpoints of the steps of the distribution function
+    Hazen,
+    // 6: p(k) = k / (n + 1), used by Minitab and SPSS
+    Weibull,
+    // 7: p(k) = (k - 1) / (n - 1), the default in R, NumPy and Excel
+    #[default]
+    Linear,
+    // 8: about the same median whatever the distribution is
+    MedianUnbiased,
+    // 9: about unbiased when the data is normally distributed
+    NormalUnbiased,
+}
+
+// Tiny differences from floating point, like n * p coming out as
+// 2.0000000000000004, shouldn't decide which value we pick
+const FUZZ: f64 = 4.0 * f64::EPSILON;
+
+impl QuantileMethod {
+    // Where the p quantile is among the sorted values, as a whole number
+    // `j` (counting from 1) and how far `h` we are from value j towards
+    // value j + 1
+    fn position(self, n: usize, p: f64) -> (usize, f64) {
+        let n = n as f64;
+        let (nppm, discontinuous) = match self {
+            QuantileMethod::InvertedCdf | QuantileMethod::AveragedInvertedCdf => (n * p, true),
+            QuantileMethod::ClosestObservation => (n * p - 0.5, true),
+            _ => {
+                let (a, b) = self.plotting_position();
+                (a + p * (n + 1.0 - a - b), false)
+            },
+        };
+
+        let j = (nppm + FUZZ).floor();
+        let h = if discontinuous {
+            let above = nppm > j;
+            match self {
+                QuantileMethod::InvertedCdf => if above { 1.0 } else { 0.0 },
+                QuantileMethod::AveragedInvertedCdf => if above { 1.0 } else { 0.5 },
+                _ => if above || j % 2.0 == 1.0 { 1.0 } else { 0.0 },
+            }
+        } else if (nppm - j).abs() < FUZZ {
+            0.0
+        } else {
+            nppm - j
+        };
+
+        // j can be 0 or -1 for p near 0, which means "before the first value"
+        (j.max(0.0) as usize, h)
+    }
+
+    // The (a, b) in p(k) = (k - a) / (n + 1 - a - b) for the continuous
+    // methods
+    fn plotting_position(self) -> (f64, f64) {
+        match self {
+            QuantileMethod::InterpolatedInvertedCdf => (0.0, 1.0),
+            QuantileMethod::Hazen => (0.5, 0.5),
+            QuantileMethod::Weibull => (0.0, 0.0),
+            QuantileMethod::MedianUnbiased => (1.0 / 3.0, 1.0 / 3.0),
+            QuantileMethod::NormalUnbiased => (3.0 / 8.0, 3.0 / 8.0),
+            _ => (1.0, 1.0),
+        }
+    }
+}
+
+// Moves the k-th smallest value (counting from 0) to v[k] and returns it.
+// Everything before v[k] ends up no larger and everything after no smaller,
+// but otherwise the slice is left unsorted. This is quickselect: like
+// quicksort, but it only keeps going into the side that holds k, so it
+// takes O(n) time on average instead of O(n log n).
+pub fn select<T: PartialOrd + Copy>(v: &mut [T], k: usize) -> T {
+    let mut lo = 0;
+    let mut hi = v.len() - 1;
+
+    while lo < hi {
+        // The median of the first, middle and last values is a much better
+        // pivot than the first value when the data is already sorted
+        let mid = lo + (hi - lo) / 2;
+        let pivot = median_of_three(v[lo], v[mid], v[hi]);
+
+        // Split v[lo..=hi] into values smaller than the pivot, equal to it
+        // and larger than it. Keeping the equal ones together means lots of
+        // repeated values don't slow us down.
+        let mut lt = lo;
+        let mut i = lo;
+        let mut gt = hi;
+        while i <= gt {
+            if v[i] < pivot {
+                v.swap(lt, i);
+                lt += 1;
+                i += 1;
+            } else if v[i] > pivot {
+                v.swap(i, gt);
+                // The pivot is somewhere in v[lo..=hi], so gt never
+                // goes below lt
+                gt -= 1;
+            } else {
+                i += 1;
+            }
+        }
+
+        if k < lt {
+            hi = lt - 1;
+        } else if k > gt {
+            lo = gt + 1;
+        } else {
+            return v[k];
+        }
+    }
+
+    v[k]
+}
+
+fn median_of_three<T: PartialOrd + Copy>(a: T, b: T, c: T) -> T {
+    if (a <= b) == (b <= c) {
+        b
+    } else if (b <= a) == (a <= c) {
+        a
+    } else {
+        c
+    }
+}
+
+// The value with 1-based position `j` in the sorted order, clamped to the
+// first and last value
+fn order_statistic(v: &mut [f64], j: usize) -> f64 {
+    let k = j.max(1).min(v.len()) - 1;
+    select(v, k)
+}
+
+// The p quantile of a slice that has no NaNs in it. The slice gets
+// reordered, which lets the next quantile reuse the work.
+fn quantile_in_place(v: &mut [f64], p: f64, method: QuantileMethod) -> f64 {
+    let (j, h) = method.position(v.len(), p);
+
+    if h == 0.0 {
+        return order_statistic(v, j);
+    }
+    if j == 0 || j >= v.len() {
+        return order_statistic(v, j + 1);
+    }
+
+    let low = select(v, j - 1);
+    // select() left every value after v[j - 1] at least as large, so the
+    // next value in sorted order is the smallest of those
+    let high = v[j..].iter().cloned().fold(f64::INFINITY, f64::min);
+    if h == 1.0 {
+        high
+    } else {
+        (1.0 - h) * low + h * high
+    }
+}
+
+// Copies the values as f64s, leaving out NaNs since they have no place in
+// the sorted order
+fn without_nans<T: Sample>(values: &[T]) -> Vec<f64> {
+    values.iter().map(|value| value.to_f64()).filter(|value| !value.is_nan()).collect()
+}
+
+// The p quantile (0 <= p <= 1) of the values. None when there are no
+// values or p is out of range. NaNs are ignored.
+pub fn quantile<T: Sample>(values: &[T], p: f64, method: QuantileMethod) -> Option<f64> {
+    quantiles(values, &[p], method).map(|q| q[0])
+}
+
+// Several quantiles at once. The values are copied only once and each
+// selection reuses the partly ordered copy from the one before.
+pub fn quantiles<T: Sample>(values: &[T], ps: &[f64], method: QuantileMethod) -> Option<Vec<f64>> {
+    if ps.iter().any(|p| !(0.0..=1.0).contains(p)) {
+        return None;
+    }
+
+    let mut v = without_nans(values);
+    if v.is_empty() {
+        return None;
+    }
+    Some(ps.iter().map(|p| quantile_in_place(&mut v, *p, method)).collect())
+}
+
+// The middle value, or the average of the two middle values
+pub fn median<T: Sample>(values: &[T]) -> Option<f64> {
+    quantile(values, 0.5, QuantileMethod::Linear)
+}
+
+// percentile(values, 95.0) is the value 95% of the data is at or below
+pub fn percentile<T: Sample>(values: &[T], percent: f64) -> Option<f64> {
+    quantile(values, percent / 100.0, QuantileMethod::Linear)
+}
+
+// The interquartile range: how wide the middle half of the data is
+pub fn iqr<T: Sample>(values: &[T]) -> Option<f64> {
+    let q = quantiles(values, &[0.25, 0.75], QuantileMethod::Linear)?;
+    Some(q[1] - q[0])
+}
+
+// The numbers a box plot is drawn from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FiveNumberSummary {
+    pub min: f64,
+    pub lower_quartile: f64,
+    pub median: f64,
+    pub upper_quartile: f64,
+    pub max: f64,
+}
+
+pub fn five_number_summary<T: Sample>(values: &[T]) -> Option<FiveNumberSummary> {
+    let q = quantiles(values, &[0.0, 0.25, 0.5, 0.75, 1.0], QuantileMethod::Linear)?;
+    Some(FiveNumberSummary {
+        min: q[0],
+        lower_quartile: q[1],
+        median: q[2],
+        upper_quartile: q[3],
+        max: q[4],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::QuantileMethod::*;
+
+    const METHODS: [QuantileMethod; 9] = [
+        InvertedCdf, AveragedInvertedCdf, ClosestObservation, InterpolatedInvertedCdf,
+        Hazen, Weibull, Linear, MedianUnbiased, NormalUnbiased,
+    ];
+
+    fn assert_all_close(expected: &[f64], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert!((e - a).abs() < 1e-9, "expected {:?}, got {:?}", expected, actual);
+        }
+    }
+
+    #[test]
+    fn select_finds_every_rank() {
+        let sorted: Vec<i32> = vec![1, 2, 2, 3, 5, 5, 5, 8, 9, 13];
+        let shuffled = vec![5, 13, 2, 9, 5, 1, 8, 3, 5, 2];
+        for (k, expected) in sorted.iter().enumerate() {
+            let mut v = shuffled.clone();
+            assert_eq!(*expected, select(&mut v, k));
+            assert!(v[..k].iter().all(|x| x <= expected));
+            assert!(v[k + 1..].iter().all(|x| x >= expected));
+        }
+    }
+
+    #[test]
+    fn select_on_sorted_and_equal_values() {
+        let mut v: Vec<u32> = (0..1000).collect();
+        assert_eq!(500, select(&mut v, 500));
+        let mut v = vec![7; 1000];
+        assert_eq!(7, select(&mut v, 999));
+    }
+
+    // Expected values are from R's quantile(x, probs, type = 1..9)
+    #[test]
+    fn all_nine_methods_odd_count() {
+        let x = [7, 1, 3, 15, 9, 4, 12, 2, 8, 20, 6];
+        let ps = [0.0, 0.1, 0.25, 0.5, 0.9, 1.0];
+        let expected = [
+            [1.0, 2.0, 3.0, 7.0, 15.0, 20.0],
+            [1.0, 2.0, 3.0, 7.0, 15.0, 20.0],
+            [1.0, 1.0, 3.0, 7.0, 15.0, 20.0],
+            [1.0, 1.1, 2.75, 6.5, 14.7, 20.0],
+            [1.0, 1.6, 3.25, 7.0, 17.0, 20.0],
+            [1.0, 1.2, 3.0, 7.0, 19.0, 20.0],
+            [1.0, 2.0, 3.5, 7.0, 15.0, 20.0],
+            [1.0, 1.4 + 0.2 / 3.0, 3.0 + 0.5 / 3.0, 7.0, 17.0 + 2.0 / 3.0, 20.0],
+            [1.0, 1.5, 3.1875, 7.0, 17.5, 20.0],
+        ];
+        for (method, expected) in METHODS.iter().zip(expected.iter()) {
+            assert_all_close(expected, &quantiles(&x, &ps, *method).unwrap());
+        }
+    }
+
+    #[test]
+    fn all_nine_methods_even_count() {
+        let x = [7.0, 1.0, 3.0, 15.0, 9.0, 4.0, 12.0, 2.0, 8.0, 20.0];
+        let ps = [0.25, 0.5, 0.95];
+        let expected = [
+            [3.0, 7.0, 20.0],
+            [3.0, 7.5, 20.0],
+            [2.0, 7.0, 20.0],
+            [2.5, 7.0, 17.5],
+            [3.0, 7.5, 20.0],
+            [2.75, 7.5, 20.0],
+            [3.25, 7.5, 17.75],
+            [2.75 + 1.0 / 6.0, 7.5, 20.0],
+            [2.9375, 7.5, 20.0],
+        ];
+        for (method, expected) in METHODS.iter().zip(expected.iter()) {
+            assert_all_close(expected, &quantiles(&x, &ps, *method).unwrap());
+        }
+    }
+
+    #[test]
+    fn one_value() {
+        for method in METHODS.iter() {
+            assert_eq!(Some(4.0), quantile(&[4], 0.3, *method));
+        }
+    }
+
+    #[test]
+    fn nothing_to_take_a_quantile_of() {
+        assert_eq!(None, median::<i32>(&[]));
+        assert_eq!(None, median(&[f64::NAN]));
+        assert_eq!(None, quantile(&[1, 2, 3], 1.5, Linear));
+        assert_eq!(None, quantile(&[1, 2, 3], f64::NAN, Linear));
+    }
+
+    #[test]
+    fn nans_are_ignored() {
+        assert_eq!(Some(2.0), median(&[f64::NAN, 3.0, 1.0, 2.0, f64::NAN]));
+    }
+
+    #[test]
+    fn latency_percentiles() {
+        // 1..=1000 milliseconds
+        let latencies: Vec<u32> = (1..=1000).rev().collect();
+        let p = [50.0, 95.0, 99.0].iter().map(|percent| percentile(&latencies, *percent).unwrap()).collect::<Vec<f64>>();
+        assert_all_close(&[500.5, 950.05, 990.01], &p);
+        assert_eq!(Some(499.5), iqr(&latencies));
+    }
+
+    #[test]
+    fn five_numbers() {
+        let summary = five_number_summary(&[6, 7, 15, 36, 39, 40, 41, 42, 43, 47, 49]).unwrap();
+        assert_eq!(
+            FiveNumberSummary {
+                min: 6.0,
+                lower_quartile: 25.5,
+                median: 40.0,
+                upper_quartile: 42.5,
+                max: 49.0,
+            },
+            summary
+        );
+    }
+}
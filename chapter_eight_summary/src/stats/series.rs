@@ -0,0 +1,533 @@
+use std::collections::VecDeque;
+
+use super::mean::CompensatedSum;
+use super::quantile::median;
+use super::summary::{Sample, Summary};
+
+// Statistics of ordered data, like a metric sampled once a second. Every
+// one is an iterator adapter, so they work on a stream as it comes in and
+// chain with each other and with the rest of Iterator:
+//
+//     latencies.iter().cloned().moving_average(60).rolling_max(10)
+//
+// The rolling ones look at the last `window` values and only start giving
+// results once they've seen that many, so they give window - 1 fewer items
+// than they're given. A window of 0 panics, like slice::windows(0) does.
+
+pub trait Series: Iterator + Sized
+where
+    Self::Item: Sample,
+{
+    // The mean of the last `window` values
+    fn moving_average(self, window: usize) -> MovingAverage<Self> {
+        MovingAverage { iter: self, window: Window::new(window), sum: CompensatedSum::new() }
+    }
+
+    // The mean of the last weights.len() values, weighted from the oldest
+    // value to the newest. [1.0, 2.0, 3.0] is the usual linearly weighted
+    // moving average over 3 values.
+    fn weighted_moving_average(self, weights: &[f64]) -> WeightedMovingAverage<Self> {
+        let total: f64 = weights.iter().sum();
+        assert!(total != 0.0, "the weights must not add up to 0");
+        WeightedMovingAverage { iter: self, window: Window::new(weights.len()), weights: weights.to_vec(), total }
+    }
+
+    // The exponentially weighted moving average: each value counts `alpha`
+    // and everything before it 1 - alpha, so old values fade away without a
+    // window to keep. Starts at the first value and gives one average per
+    // value. Panics unless 0 < alpha <= 1.
+    fn ewma(self, alpha: f64) -> Ewma<Self> {
+        assert!(alpha > 0.0 && alpha <= 1.0, "alpha must be above 0 and at most 1");
+        Ewma { iter: self, alpha, average: None }
+    }
+
+    // The smallest of the last `window` values
+    fn rolling_min(self, window: usize) -> RollingExtreme<Self> {
+        RollingExtreme::new(self, window, |new, old| new <= old)
+    }
+
+    // The largest of the last `window` values
+    fn rolling_max(self, window: usize) -> RollingExtreme<Self> {
+        RollingExtreme::new(self, window, |new, old| new >= old)
+    }
+
+    // The sample standard deviation of the last `window` values. A window
+    // of 1 has no spread to measure, so it gives nothing at all.
+    fn rolling_std(self, window: usize) -> RollingStd<Self> {
+        RollingStd { iter: self, window: Window::new(window) }
+    }
+
+    // Scores every value by how many standard deviations it is from the
+    // mean of the `window` values before it, and flags it as an outlier
+    // past `threshold` (3 is usual). The value itself isn't in the window,
+    // so a spike can't hide by dragging the mean and spread towards itself.
+    fn zscore_outliers(self, window: usize, threshold: f64) -> Outliers<Self> {
+        Outliers::new(self, window, threshold, Scoring::ZScore)
+    }
+
+    // Like zscore_outliers(), but from the median and the median absolute
+    // deviation (MAD) of the window, which a few outliers already in it
+    // barely move. The score is Iglewicz and Hoaglin's modified z-score,
+    // which is scaled to match the z-score for normal data; they suggest
+    // 3.5 as the threshold.
+    fn mad_outliers(self, window: usize, threshold: f64) -> Outliers<Self> {
+        Outliers::new(self, window, threshold, Scoring::Mad)
+    }
+}
+
+impl<I: Iterator> Series for I where I::Item: Sample {}
+
+// The last `size` values
+struct Window {
+    values: VecDeque<f64>,
+    size: usize,
+}
+
+impl Window {
+    fn new(size: usize) -> Window {
+        assert!(size > 0, "the window must hold at least one value");
+        Window { values: VecDeque::with_capacity(size + 1), size }
+    }
+
+    // Adds a value, giving back the one that dropped out of the window
+    fn push(&mut self, value: f64) -> Option<f64> {
+        self.values.push_back(value);
+        if self.values.len() > self.size {
+            self.values.pop_front()
+        } else {
+            None
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.values.len() == self.size
+    }
+}
+
+pub struct MovingAverage<I> {
+    iter: I,
+    window: Window,
+    // The sum of the values in the window, kept up to date as they come
+    // and go instead of adding the whole window up again every time. Once
+    // it's infinite or NaN, taking a value back out can't undo that, so
+    // then it's added up again until the window is back to normal values.
+    sum: CompensatedSum,
+}
+
+impl<I: Iterator> Iterator for MovingAverage<I>
+where
+    I::Item: Sample,
+{
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        loop {
+            let value = self.iter.next()?.to_f64();
+            self.sum.add(value);
+            let old = self.window.push(value);
+            if !self.sum.value().is_finite() {
+                self.sum = CompensatedSum::new();
+                for value in self.window.values.iter() {
+                    self.sum.add(*value);
+                }
+            } else if let Some(old) = old {
+                self.sum.add(-old);
+            }
+            if self.window.is_full() {
+                return Some(self.sum.value() / self.window.size as f64);
+            }
+        }
+    }
+}
+
+pub struct WeightedMovingAverage<I> {
+    iter: I,
+    window: Window,
+    weights: Vec<f64>,
+    total: f64,
+}
+
+impl<I: Iterator> Iterator for WeightedMovingAverage<I>
+where
+    I::Item: Sample,
+{
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        loop {
+            self.window.push(self.iter.next()?.to_f64());
+            if self.window.is_full() {
+                let mut sum = CompensatedSum::new();
+                for (value, weight) in self.window.values.iter().zip(self.weights.iter()) {
+                    sum.add(value * weight);
+                }
+                return Some(sum.value() / self.total);
+            }
+        }
+    }
+}
+
+pub struct Ewma<I> {
+    iter: I,
+    alpha: f64,
+    average: Option<f64>,
+}
+
+impl<I: Iterator> Iterator for Ewma<I>
+where
+    I::Item: Sample,
+{
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        let value = self.iter.next()?.to_f64();
+        let average = match self.average {
+            Some(average) => average + self.alpha * (value - average),
+            None => value,
+        };
+        self.average = Some(average);
+        Some(average)
+    }
+}
+
+pub struct RollingExtreme<I> {
+    iter: I,
+    size: usize,
+    seen: usize,
+    // The values that could still become the extreme as older ones leave
+    // the window, with when they came. Each one beats every value after
+    // it, so the extreme is always at the front. A new value knocks off
+    // every value at the back it beats, since those will never be the
+    // extreme again, which makes this O(1) per value on average. NaN is
+    // skipped like Summary skips it, so a window of nothing but NaN has no
+    // candidates and its extreme is NaN.
+    candidates: VecDeque<(usize, f64)>,
+    beats: fn(f64, f64) -> bool,
+}
+
+impl<I> RollingExtreme<I> {
+    fn new(iter: I, size: usize, beats: fn(f64, f64) -> bool) -> RollingExtreme<I> {
+        assert!(size > 0, "the window must hold at least one value");
+        RollingExtreme { iter, size, seen: 0, candidates: VecDeque::new(), beats }
+    }
+}
+
+impl<I: Iterator> Iterator for RollingExtreme<I>
+where
+    I::Item: Sample,
+{
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        loop {
+            let value = self.iter.next()?.to_f64();
+            let index = self.seen;
+            self.seen += 1;
+
+            if !value.is_nan() {
+                while let Some((_, last)) = self.candidates.back() {
+                    if (self.beats)(value, *last) {
+                        self.candidates.pop_back();
+                    } else {
+                        break;
+                    }
+                }
+                self.candidates.push_back((index, value));
+            }
+            if self.candidates.front().is_some_and(|(first, _)| first + self.size <= index) {
+                self.candidates.pop_front();
+            }
+
+            if self.seen >= self.size {
+                return Some(self.candidates.front().map_or(f64::NAN, |(_, extreme)| *extreme));
+            }
+        }
+    }
+}
+
+pub struct RollingStd<I> {
+    iter: I,
+    window: Window,
+}
+
+impl<I: Iterator> Iterator for RollingStd<I>
+where
+    I::Item: Sample,
+{
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        loop {
+            self.window.push(self.iter.next()?.to_f64());
+            // Taking the oldest value back out of a running variance loses
+            // precision every time, so work it out afresh for each window
+            if self.window.is_full() {
+                let summary: Summary<f64> = self.window.values.iter().cloned().collect();
+                if let Some(std_dev) = summary.std_dev() {
+                    return Some(std_dev);
+                }
+            }
+        }
+    }
+}
+
+// A value and how unusual it is next to the ones before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scored {
+    pub value: f64,
+    // None until there's a full window to compare with. Infinite when every
+    // value in the window was the same and this one is different.
+    pub score: Option<f64>,
+    pub outlier: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Scoring {
+    ZScore,
+    Mad,
+}
+
+pub struct Outliers<I> {
+    iter: I,
+    window: Window,
+    threshold: f64,
+    scoring: Scoring,
+}
+
+impl<I> Outliers<I> {
+    fn new(iter: I, window: usize, threshold: f64, scoring: Scoring) -> Outliers<I> {
+        Outliers { iter, window: Window::new(window), threshold, scoring }
+    }
+
+    // How far `value` is from the middle of the window, in units of its
+    // spread. None when the window isn't full yet, or a window of one value
+    // has no standard deviation.
+    fn score(&self, value: f64) -> Option<f64> {
+        if !self.window.is_full() {
+            return None;
+        }
+        let values: Vec<f64> = self.window.values.iter().cloned().collect();
+        let (middle, spread) = match self.scoring {
+            Scoring::ZScore => {
+                let summary: Summary<f64> = values.iter().cloned().collect();
+                (summary.mean()?, summary.std_dev()?)
+            },
+            Scoring::Mad => {
+                let middle = median(&values)?;
+                let deviations: Vec<f64> = values.iter().map(|v| (v - middle).abs()).collect();
+                // The MAD of normal data is 0.6745 standard deviations. It's
+                // 0 whenever over half the window is one value, which is
+                // common for counts, so then Iglewicz and Hoaglin use the
+                // mean absolute deviation, 0.7979 standard deviations.
+                match median(&deviations)? {
+                    mad if mad > 0.0 => (middle, mad / 0.6745),
+                    _ => (middle, deviations.iter().sum::<f64>() / deviations.len() as f64 * 1.2533),
+                }
+            },
+        };
+
+        let distance = value - middle;
+        if spread == 0.0 {
+            return Some(if distance == 0.0 { 0.0 } else { distance.signum() * f64::INFINITY });
+        }
+        Some(distance / spread)
+    }
+}
+
+impl<I: Iterator> Iterator for Outliers<I>
+where
+    I::Item: Sample,
+{
+    type Item = Scored;
+
+    fn next(&mut self) -> Option<Scored> {
+        let value = self.iter.next()?.to_f64();
+        let score = self.score(value);
+        self.window.push(value);
+        Some(Scored {
+            value,
+            score,
+            outlier: score.is_some_and(|score| score.abs() > self.threshold),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn assert_all_close(expected: &[f64], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len(), "expected {:?}, got {:?}", expected, actual);
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert!((e - a).abs() < 1e-9, "expected {:?}, got {:?}", expected, actual);
+        }
+    }
+
+    #[test]
+    fn moving_averages() {
+        let values = [1, 2, 3, 4, 5, 6];
+        let simple: Vec<f64> = values.iter().cloned().moving_average(3).collect();
+        assert_all_close(&[2.0, 3.0, 4.0, 5.0], &simple);
+
+        let weighted: Vec<f64> = values.iter().cloned().weighted_moving_average(&[1.0, 2.0, 3.0]).collect();
+        // (1 * 1 + 2 * 2 + 3 * 3) / 6, and so on
+        assert_all_close(&[14.0 / 6.0, 20.0 / 6.0, 26.0 / 6.0, 32.0 / 6.0], &weighted);
+
+        // A window longer than the data never fills
+        assert_eq!(0, values.iter().cloned().moving_average(10).count());
+        // Equal weights are the simple moving average
+        let equal: Vec<f64> = values.iter().cloned().weighted_moving_average(&[1.0; 3]).collect();
+        assert_all_close(&simple, &equal);
+    }
+
+    #[test]
+    fn moving_average_recovers_from_non_finite_values() {
+        let values = [1.0, f64::NAN, 2.0, 3.0, f64::INFINITY, 4.0, 5.0, 6.0];
+        let averages: Vec<f64> = values.iter().cloned().moving_average(2).collect();
+        assert!(averages[..2].iter().all(|a| a.is_nan()));
+        assert_eq!(&[2.5, f64::INFINITY, f64::INFINITY, 4.5, 5.5], &averages[2..]);
+
+        // Big values that overflow the sum together, but not once they're gone
+        let values = [f64::MAX, f64::MAX, 1.0, 2.0];
+        let averages: Vec<f64> = values.iter().cloned().moving_average(2).collect();
+        assert_eq!(vec![f64::INFINITY, f64::MAX / 2.0, 1.5], averages);
+    }
+
+    #[test]
+    fn exponential_moving_average() {
+        let averages: Vec<f64> = [10.0, 20.0, 20.0, 0.0].iter().cloned().ewma(0.5).collect();
+        assert_all_close(&[10.0, 15.0, 17.5, 8.75], &averages);
+
+        // With alpha = 1 only the latest value counts
+        let latest: Vec<f64> = [3, 1, 4].iter().cloned().ewma(1.0).collect();
+        assert_all_close(&[3.0, 1.0, 4.0], &latest);
+    }
+
+    #[test]
+    #[should_panic(expected = "window")]
+    fn empty_window() {
+        [1.0].iter().cloned().moving_average(0).count();
+    }
+
+    #[test]
+    #[should_panic(expected = "alpha")]
+    fn alpha_out_of_range() {
+        [1.0].iter().cloned().ewma(0.0).count();
+    }
+
+    #[test]
+    fn rolling_extremes() {
+        let values = [5, 3, 4, 1, 2, 6, 6, 0];
+        let mins: Vec<f64> = values.iter().cloned().rolling_min(3).collect();
+        let maxes: Vec<f64> = values.iter().cloned().rolling_max(3).collect();
+        assert_eq!(vec![3.0, 1.0, 1.0, 1.0, 2.0, 0.0], mins);
+        assert_eq!(vec![5.0, 4.0, 4.0, 6.0, 6.0, 6.0], maxes);
+
+        // NaN is skipped wherever it is in the window
+        let values = [1.0, f64::NAN, 3.0, 2.0];
+        let maxes: Vec<f64> = values.iter().cloned().rolling_max(2).collect();
+        let mins: Vec<f64> = values.iter().cloned().rolling_min(2).collect();
+        assert_eq!(vec![1.0, 3.0, 3.0], maxes);
+        assert_eq!(vec![1.0, 3.0, 2.0], mins);
+
+        let values = [f64::NAN, f64::NAN, 4.0];
+        let maxes: Vec<f64> = values.iter().cloned().rolling_max(2).collect();
+        assert!(maxes[0].is_nan());
+        assert_eq!(4.0, maxes[1]);
+    }
+
+    #[test]
+    fn rolling_standard_deviation() {
+        let values = [2, 4, 4, 4, 5, 5, 7, 9];
+        let std_devs: Vec<f64> = values.iter().cloned().rolling_std(8).collect();
+        assert_all_close(&[(32.0f64 / 7.0).sqrt()], &std_devs);
+
+        let std_devs: Vec<f64> = values.iter().cloned().rolling_std(2).collect();
+        let halves = 0.5f64.sqrt();
+        assert_all_close(&[2.0 * halves, 0.0, 0.0, halves, 0.0, 2.0 * halves, 2.0 * halves], &std_devs);
+
+        assert_eq!(0, values.iter().cloned().rolling_std(1).count());
+    }
+
+    #[test]
+    fn adapters_chain() {
+        // Smooth a noisy ramp, then find the biggest step in the smoothed
+        // series over any 3 values
+        let noisy = [0.0, 2.0, 1.0, 3.0, 2.0, 4.0, 3.0, 5.0];
+        let steps: Vec<f64> = noisy.iter().cloned().moving_average(2).rolling_max(3).collect();
+        assert_all_close(&[2.0, 2.5, 3.0, 3.5, 4.0], &steps);
+    }
+
+    #[test]
+    fn spikes_are_outliers() {
+        let mut latencies = vec![10.0, 12.0, 11.0, 9.0, 10.0, 11.0, 12.0, 10.0];
+        latencies.push(60.0);
+        latencies.push(11.0);
+
+        for scored in [
+            latencies.iter().cloned().zscore_outliers(5, 3.0).collect::<Vec<Scored>>(),
+            latencies.iter().cloned().mad_outliers(5, 3.5).collect::<Vec<Scored>>(),
+        ]
+        .iter()
+        {
+            assert_eq!(latencies.len(), scored.len());
+            assert_eq!(None, scored[4].score);
+            assert!(scored[5].score.is_some());
+            let outliers: Vec<f64> = scored.iter().filter(|s| s.outlier).map(|s| s.value).collect();
+            assert_eq!(vec![60.0], outliers);
+        }
+    }
+
+    #[test]
+    fn mad_shrugs_off_earlier_outliers() {
+        // A spike in the window inflates the standard deviation enough to
+        // hide the second one, but barely moves the MAD
+        let values = [10.0, 11.0, 10.0, 50.0, 11.0, 10.0, 48.0];
+        let z: Vec<Scored> = values.iter().cloned().zscore_outliers(6, 3.0).collect();
+        let mad: Vec<Scored> = values.iter().cloned().mad_outliers(6, 3.5).collect();
+        assert!(!z[6].outlier);
+        assert!(mad[6].outlier);
+    }
+
+    #[test]
+    fn flat_windows() {
+        let scored: Vec<Scored> = [5, 5, 5, 5, 6].iter().cloned().zscore_outliers(3, 3.0).collect();
+        assert_eq!(Some(0.0), scored[3].score);
+        assert_eq!(Some(f64::INFINITY), scored[4].score);
+        assert!(scored[4].outlier);
+    }
+
+    #[test]
+    fn mad_of_a_mostly_flat_window() {
+        // The MAD is 0, but the window does vary, so 11 isn't infinitely far
+        let scored: Vec<Scored> = [10, 10, 10, 11, 10, 11, 14].iter().cloned().mad_outliers(5, 3.5).collect();
+        // The mean absolute deviation from 10 is 0.2
+        assert_all_close(&[1.0 / (0.2 * 1.2533)], &[scored[5].score.unwrap()]);
+        assert!(scored[5].outlier);
+        assert!(scored[6].score.unwrap().is_finite());
+
+        let scored: Vec<Scored> = [5, 5, 5, 6].iter().cloned().mad_outliers(3, 3.5).collect();
+        assert_eq!(Some(f64::INFINITY), scored[3].score);
+    }
+
+    proptest! {
+        #[test]
+        fn rolling_matches_each_window(values in prop::collection::vec(-1000i32..1000, 0..60), window in 1usize..8) {
+            let windows: Vec<&[i32]> = values.windows(window).collect();
+
+            let mins: Vec<f64> = values.iter().cloned().rolling_min(window).collect();
+            let maxes: Vec<f64> = values.iter().cloned().rolling_max(window).collect();
+            let averages: Vec<f64> = values.iter().cloned().moving_average(window).collect();
+            prop_assert_eq!(windows.len(), mins.len());
+            prop_assert_eq!(windows.len(), averages.len());
+
+            for (i, w) in windows.iter().enumerate() {
+                prop_assert_eq!(*w.iter().min().unwrap() as f64, mins[i]);
+                prop_assert_eq!(*w.iter().max().unwrap() as f64, maxes[i]);
+                let mean = w.iter().map(|v| *v as f64).sum::<f64>() / window as f64;
+                prop_assert!((mean - averages[i]).abs() < 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use rand::rngs::SmallRng;
+use rand::{FromEntropy, Rng, SeedableRng};
+
+use super::summary::{is_new_max, is_new_min, Sample};
+
+// Each level above the bottom one holds about 2/3 of what the level below
+// it can, which is what keeps the whole sketch at O(k) values
+const SHRINK: f64 = 2.0 / 3.0;
+
+// Approximate quantiles of data too big to keep: a KLL sketch (Karnin,
+// Lang and Liberty, "Optimal Quantile Approximation in Streams", 2016).
+//
+// The sketch is a stack of levels. New values go into level 0, and every
+// value in level h stands for 2^h of the original values. When a level is
+// full it gets sorted and every other value (starting at a random one of
+// the first two) moves up a level, so half the values are thrown away but
+// each kept one now counts twice. The coin flip means the errors cancel
+// out instead of piling up.
+//
+// With `k` values in the top level a quantile's rank is off by no more
+// than about error_bound() * count() with high probability, while the
+// sketch only ever holds about 3k values. Two sketches can be merged, so
+// the data can be split up and summarised in parallel.
+#[derive(Debug, Clone)]
+pub struct QuantileSketch<T> {
+    k: usize,
+    levels: Vec<Vec<T>>,
+    // How many values are stored in the levels, and how many fit in total
+    size: usize,
+    max_size: usize,
+    count: u64,
+    min: Option<T>,
+    max: Option<T>,
+    rng: SmallRng,
+}
+
+impl<T: Sample> QuantileSketch<T> {
+    // A bigger k gives more accurate quantiles for more memory. k = 200
+    // keeps ranks within about 1.3%.
+    pub fn new(k: usize) -> QuantileSketch<T> {
+        let mut sketch = QuantileSketch {
+            k: k.max(2),
+            levels: Vec::new(),
+            size: 0,
+            max_size: 0,
+            count: 0,
+            min: None,
+            max: None,
+            rng: SmallRng::from_entropy(),
+        };
+        sketch.grow();
+        sketch
+    }
+
+    // The smallest sketch whose ranks are off by at most `epsilon` (e.g.
+    // 0.01 for 1%) of the count
+    pub fn with_error(epsilon: f64) -> QuantileSketch<T> {
+        QuantileSketch::new(k_for_error(epsilon))
+    }
+
+    // Use a fixed seed for the coin flips so the same data always gives the
+    // same sketch
+    pub fn seeded(mut self, seed: u64) -> QuantileSketch<T> {
+        self.rng = SmallRng::seed_from_u64(seed);
+        self
+    }
+
+    // About how far off (as a fraction of the count) the rank of any
+    // quantile can be. The constants come from fitting the measured error
+    // of KLL sketches, as done by Apache DataSketches.
+    pub fn error_bound(&self) -> f64 {
+        2.296 / (self.k as f64).powf(0.9723)
+    }
+
+    pub fn add(&mut self, value: T) {
+        // NaN has no place in the sorted order
+        if value.to_f64().is_nan() {
+            return;
+        }
+        if is_new_min(value, self.min) {
+            self.min = Some(value);
+        }
+        if is_new_max(value, self.max) {
+            self.max = Some(value);
+        }
+
+        self.count += 1;
+        self.levels[0].push(value);
+        self.size += 1;
+        if self.size >= self.max_size {
+            self.compress();
+        }
+    }
+
+    // Combines two sketches, as if every value of `other` had been added
+    // to this one
+    pub fn merge(&mut self, other: &QuantileSketch<T>) {
+        while self.levels.len() < other.levels.len() {
+            self.grow();
+        }
+        for (level, values) in other.levels.iter().enumerate() {
+            self.levels[level].extend(values.iter().cloned());
+        }
+
+        self.count += other.count;
+        if let Some(min) = other.min {
+            if is_new_min(min, self.min) {
+                self.min = Some(min);
+            }
+        }
+        if let Some(max) = other.max {
+            if is_new_max(max, self.max) {
+                self.max = Some(max);
+            }
+        }
+
+        self.size = self.levels.iter().map(|values| values.len()).sum();
+        while self.size >= self.max_size {
+            self.compress();
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.max
+    }
+
+    // About what fraction of the values are at or below `value`
+    pub fn rank(&self, value: T) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let mut weight = 0u64;
+        for (level, values) in self.levels.iter().enumerate() {
+            let at_or_below = values.iter().filter(|v| **v <= value).count() as u64;
+            weight += at_or_below << level;
+        }
+        weight as f64 / self.count as f64
+    }
+
+    // About the p quantile (0 <= p <= 1): a value with roughly p of the data
+    // at or below it. The ends are exact, since we keep the min and max.
+    // None when the sketch is empty or p is out of range.
+    pub fn quantile(&self, p: f64) -> Option<T> {
+        self.quantiles(&[p]).map(|q| q[0])
+    }
+
+    // Several quantiles at once, sorting the sketch only once
+    pub fn quantiles(&self, ps: &[f64]) -> Option<Vec<T>> {
+        if self.count == 0 || ps.iter().any(|p| !(0.0..=1.0).contains(p)) {
+            return None;
+        }
+
+        let mut weighted: Vec<(T, u64)> = Vec::with_capacity(self.size);
+        for (level, values) in self.levels.iter().enumerate() {
+            weighted.extend(values.iter().map(|v| (*v, 1u64 << level)));
+        }
+        weighted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let quantiles = ps
+            .iter()
+            .map(|p| {
+                if *p == 0.0 {
+                    return self.min.unwrap();
+                }
+                if *p == 1.0 {
+                    return self.max.unwrap();
+                }
+                let target = p * self.count as f64;
+                let mut cumulative = 0u64;
+                for (value, weight) in weighted.iter() {
+                    cumulative += weight;
+                    if cumulative as f64 >= target {
+                        return *value;
+                    }
+                }
+                self.max.unwrap()
+            })
+            .collect();
+        Some(quantiles)
+    }
+
+    // How many values level h can hold before it gets compacted. The top
+    // level holds k and each level below holds 2/3 of the one above.
+    fn capacity(&self, level: usize) -> usize {
+        let depth = self.levels.len() - level - 1;
+        (self.k as f64 * SHRINK.powi(depth as i32)).ceil() as usize + 1
+    }
+
+    fn grow(&mut self) {
+        self.levels.push(Vec::new());
+        self.max_size = (0..self.levels.len()).map(|level| self.capacity(level)).sum();
+    }
+
+    // Compacts the lowest full level into the one above it
+    fn compress(&mut self) {
+        for level in 0..self.levels.len() {
+            if self.levels[level].len() < self.capacity(level) {
+                continue;
+            }
+            if level + 1 == self.levels.len() {
+                self.grow();
+            }
+
+            let mut values = std::mem::take(&mut self.levels[level]);
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            // With an odd number of values, the largest one waits in this
+            // level for the next compaction
+            if values.len() % 2 == 1 {
+                self.levels[level].push(values.pop().unwrap());
+            }
+            let offset = if self.rng.gen::<bool>() { 1 } else { 0 };
+            let promoted: Vec<T> = values.into_iter().skip(offset).step_by(2).collect();
+            self.levels[level + 1].extend(promoted);
+
+            self.size = self.levels.iter().map(|values| values.len()).sum();
+            if self.size < self.max_size {
+                break;
+            }
+        }
+    }
+}
+
+// The inverse of error_bound()
+fn k_for_error(epsilon: f64) -> usize {
+    (2.296 / epsilon).powf(1.0 / 0.9723).ceil() as usize
+}
+
+impl<T: Sample> Extend<T> for QuantileSketch<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PS: [f64; 11] = [0.01, 0.05, 0.1, 0.25, 0.5, 0.75, 0.9, 0.95, 0.99, 0.999, 0.3333];
+
+    // How far the rank of `value` in the sorted data is from p, as a
+    // fraction of the count. Values that appear more than once cover a range
+    // of ranks, and anywhere in that range counts as no error.
+    fn rank_error<T: Sample>(sorted: &[T], value: T, p: f64) -> f64 {
+        let n = sorted.len() as f64;
+        let below = sorted.iter().filter(|v| **v < value).count() as f64 / n;
+        let at_or_below = sorted.iter().filter(|v| **v <= value).count() as f64 / n;
+        if p < below {
+            below - p
+        } else if p > at_or_below {
+            p - at_or_below
+        } else {
+            0.0
+        }
+    }
+
+    fn check_sketch<T: Sample + std::fmt::Debug>(mut values: Vec<T>, sketch: &QuantileSketch<T>) {
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let quantiles = sketch.quantiles(&PS).unwrap();
+        for (p, q) in PS.iter().zip(quantiles.iter()) {
+            let error = rank_error(&values, *q, *p);
+            assert!(error <= sketch.error_bound(), "p = {}: got {:?}, rank off by {}", p, q, error);
+        }
+        assert_eq!(values.len() as u64, sketch.count());
+        assert_eq!(values.first().cloned(), sketch.min());
+        assert_eq!(values.last().cloned(), sketch.max());
+    }
+
+    #[test]
+    fn empty_sketch() {
+        let sketch: QuantileSketch<f64> = QuantileSketch::new(100);
+        assert_eq!(None, sketch.quantile(0.5));
+        assert_eq!(0.0, sketch.rank(1.0));
+    }
+
+    #[test]
+    fn small_data_is_exact() {
+        let mut sketch = QuantileSketch::new(200).seeded(1);
+        sketch.extend(vec![5, 1, 4, 2, 3]);
+        assert_eq!(Some(vec![1, 3, 5]), sketch.quantiles(&[0.0, 0.5, 1.0]));
+        assert_eq!(0.4, sketch.rank(2));
+        assert_eq!(None, sketch.quantile(1.5));
+    }
+
+    #[test]
+    fn accuracy_matches_k() {
+        let sketch: QuantileSketch<u8> = QuantileSketch::with_error(0.01);
+        assert!(sketch.error_bound() <= 0.01);
+        assert!(QuantileSketch::<u8>::new(sketch.k - 1).error_bound() > 0.01);
+    }
+
+    #[test]
+    fn uniform_latencies() {
+        let mut rng = SmallRng::seed_from_u64(36);
+        let values: Vec<u32> = (0..200_000).map(|_| rng.gen_range(1, 5000)).collect();
+
+        let mut sketch = QuantileSketch::new(200).seeded(7);
+        sketch.extend(values.iter().cloned());
+        check_sketch(values, &sketch);
+        assert!(sketch.size < 3 * 200 + 64, "sketch holds {} values", sketch.size);
+    }
+
+    #[test]
+    fn skewed_floats() {
+        // Exponentially distributed, like most latencies: lots of small
+        // values and a long tail
+        let mut rng = SmallRng::seed_from_u64(360);
+        let values: Vec<f64> = (0..100_000).map(|_| -(1.0 - rng.gen::<f64>()).ln() * 20.0).collect();
+
+        let mut sketch = QuantileSketch::with_error(0.005).seeded(11);
+        sketch.extend(values.iter().cloned());
+        check_sketch(values, &sketch);
+    }
+
+    #[test]
+    fn sorted_input() {
+        let values: Vec<i64> = (0..100_000).collect();
+        let mut sketch = QuantileSketch::new(100).seeded(3);
+        sketch.extend(values.iter().cloned());
+        check_sketch(values, &sketch);
+    }
+
+    #[test]
+    fn merged_sketches() {
+        let mut rng = SmallRng::seed_from_u64(3600);
+        let mut all = Vec::new();
+        let mut merged = QuantileSketch::new(200).seeded(5);
+
+        // Four parts of very different sizes and ranges
+        for (part, size) in [50_000, 10, 120_000, 3_000].iter().enumerate() {
+            let values: Vec<i32> = (0..*size).map(|_| rng.gen_range(0, 1000) + 500 * part as i32).collect();
+            let mut sketch = QuantileSketch::new(200).seeded(part as u64);
+            sketch.extend(values.iter().cloned());
+            merged.merge(&sketch);
+            all.extend(values);
+        }
+
+        check_sketch(all, &merged);
+    }
+}
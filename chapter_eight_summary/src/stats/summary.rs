@@ -0,0 +1,338 @@
+use std::iter::FromIterator;
+
+use super::mean::CompensatedSum;
+
+// Anything we can take statistics of. Every calculation is done in f64,
+// so all a type needs is a way to become one (and to be compared for the
+// minimum and maximum).
+pub trait Sample: Copy + PartialOrd {
+    fn to_f64(self) -> f64;
+}
+
+// Writing the same impl for every number type by hand would be a lot of
+// copy and paste, so a macro writes them for us
+macro_rules! impl_sample {
+    ($($t:ty),*) => {
+        $(
+            impl Sample for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_sample!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+// Descriptive statistics collected in a single pass. Values are added one
+// at a time and nothing is stored, so a Summary works on data of any size,
+// even data that never fits in a Vec.
+//
+// The mean and the central moments (m2, m3, m4) are updated with Welford's
+// method. Adding up squares and subtracting at the end loses almost all
+// precision when the values are large compared to their spread; Welford's
+// updates don't have that problem.
+#[derive(Debug, Clone)]
+pub struct Summary<T> {
+    count: u64,
+    min: Option<T>,
+    max: Option<T>,
+    sum: CompensatedSum,
+    mean: f64,
+    // Sums of the 2nd, 3rd and 4th powers of the distance from the mean
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+
+impl<T: Sample> Summary<T> {
+    pub fn new() -> Summary<T> {
+        Summary {
+            count: 0,
+            min: None,
+            max: None,
+            sum: CompensatedSum::new(),
+            mean: 0.0,
+            m2: 0.0,
+            m3: 0.0,
+            m4: 0.0,
+        }
+    }
+
+    pub fn add(&mut self, value: T) {
+        if is_new_min(value, self.min) {
+            self.min = Some(value);
+        }
+        if is_new_max(value, self.max) {
+            self.max = Some(value);
+        }
+
+        let x = value.to_f64();
+        self.sum.add(x);
+
+        // Terriberry's extension of Welford's method to the higher moments.
+        // The order matters: m4 uses the old m3 and m2, m3 uses the old m2.
+        let n1 = self.count as f64;
+        self.count += 1;
+        let n = self.count as f64;
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term1 = delta * delta_n * n1;
+
+        self.mean += delta_n;
+        self.m4 += term1 * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * self.m2 - 4.0 * delta_n * self.m3;
+        self.m3 += term1 * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term1;
+    }
+
+    // Combines two summaries, as if every value of `other` had been added
+    // to this one. Handy for summarising chunks of data in parallel.
+    pub fn merge(&mut self, other: &Summary<T>) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = other.clone();
+            return;
+        }
+
+        if let Some(min) = other.min {
+            if is_new_min(min, self.min) {
+                self.min = Some(min);
+            }
+        }
+        if let Some(max) = other.max {
+            if is_new_max(max, self.max) {
+                self.max = Some(max);
+            }
+        }
+        self.sum.merge(&other.sum);
+
+        // Pebay's formulas for combining central moments of two sets
+        let na = self.count as f64;
+        let nb = other.count as f64;
+        let n = na + nb;
+        let delta = other.mean - self.mean;
+        let delta2 = delta * delta;
+        let delta3 = delta2 * delta;
+        let delta4 = delta2 * delta2;
+
+        let m2 = self.m2 + other.m2 + delta2 * na * nb / n;
+        let m3 = self.m3 + other.m3 + delta3 * na * nb * (na - nb) / (n * n)
+            + 3.0 * delta * (na * other.m2 - nb * self.m2) / n;
+        let m4 = self.m4 + other.m4 + delta4 * na * nb * (na * na - na * nb + nb * nb) / (n * n * n)
+            + 6.0 * delta2 * (na * na * other.m2 + nb * nb * self.m2) / (n * n)
+            + 4.0 * delta * (na * other.m3 - nb * self.m3) / n;
+
+        self.count += other.count;
+        self.mean += delta * nb / n;
+        self.m2 = m2;
+        self.m3 = m3;
+        self.m4 = m4;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.max
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum.value()
+    }
+
+    // Everything below has no answer for an empty summary, so it returns
+    // None instead of NaN
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.mean)
+        }
+    }
+
+    // Sample variance, dividing by n - 1. Needs at least two values.
+    pub fn variance(&self) -> Option<f64> {
+        if self.count < 2 {
+            None
+        } else {
+            Some(self.m2 / (self.count - 1) as f64)
+        }
+    }
+
+    // Variance of the values themselves as the whole population, dividing by n
+    pub fn population_variance(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.m2 / self.count as f64)
+        }
+    }
+
+    // Sample standard deviation
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    // How lopsided the data is: positive when the tail on the right is
+    // longer. None when every value is the same, since there is no spread.
+    pub fn skewness(&self) -> Option<f64> {
+        if self.count == 0 || self.m2 == 0.0 {
+            None
+        } else {
+            Some((self.count as f64).sqrt() * self.m3 / self.m2.powf(1.5))
+        }
+    }
+
+    // Excess kurtosis: 0 for a normal distribution, positive when there are
+    // more values far from the mean than a normal distribution would have
+    pub fn kurtosis(&self) -> Option<f64> {
+        if self.count == 0 || self.m2 == 0.0 {
+            None
+        } else {
+            Some(self.count as f64 * self.m4 / (self.m2 * self.m2) - 3.0)
+        }
+    }
+}
+
+// NaN is neither smaller nor larger than anything, so it never replaces
+// a minimum or maximum once there is one
+pub fn is_new_min<T: Sample>(value: T, min: Option<T>) -> bool {
+    match min {
+        Some(min) => value < min,
+        None => true,
+    }
+}
+
+pub fn is_new_max<T: Sample>(value: T, max: Option<T>) -> bool {
+    match max {
+        Some(max) => value > max,
+        None => true,
+    }
+}
+
+impl<T: Sample> Default for Summary<T> {
+    fn default() -> Summary<T> {
+        Summary::new()
+    }
+}
+
+impl<T: Sample> Extend<T> for Summary<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+// Lets us write `numbers.iter().copied().collect::<Summary<i32>>()`
+impl<T: Sample> FromIterator<T> for Summary<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Summary<T> {
+        let mut summary = Summary::new();
+        summary.extend(iter);
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: f64, actual: Option<f64>) {
+        let actual = actual.expect("expected a value");
+        assert!((expected - actual).abs() <= 1e-9 * expected.abs().max(1.0), "expected {}, got {}", expected, actual);
+    }
+
+    // The textbook two-pass calculations to compare against
+    fn two_pass(values: &[f64]) -> (f64, f64, f64, f64) {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let moment = |p: i32| values.iter().map(|x| (x - mean).powi(p)).sum::<f64>() / n;
+        let (m2, m3, m4) = (moment(2), moment(3), moment(4));
+        (mean, m2 * n / (n - 1.0), m3 / m2.powf(1.5), m4 / (m2 * m2) - 3.0)
+    }
+
+    #[test]
+    fn empty_summary() {
+        let summary: Summary<i32> = Summary::new();
+        assert_eq!(0, summary.count());
+        assert_eq!(None, summary.min());
+        assert_eq!(None, summary.mean());
+        assert_eq!(None, summary.variance());
+        assert_eq!(0.0, summary.sum());
+    }
+
+    #[test]
+    fn integers() {
+        let summary: Summary<i32> = vec![2, 4, 4, 4, 5, 5, 7, 9].into_iter().collect();
+        assert_eq!(8, summary.count());
+        assert_eq!(Some(2), summary.min());
+        assert_eq!(Some(9), summary.max());
+        assert_eq!(40.0, summary.sum());
+        assert_close(5.0, summary.mean());
+        assert_close(4.0, summary.population_variance());
+        assert_close(32.0 / 7.0, summary.variance());
+        assert_close((32.0f64 / 7.0).sqrt(), summary.std_dev());
+    }
+
+    #[test]
+    fn floats_match_two_pass() {
+        let values = vec![1.5, -2.25, 3.0, 10.0, 0.125, 7.5, 7.5, -4.0, 2.0];
+        let summary: Summary<f64> = values.iter().copied().collect();
+        let (mean, variance, skewness, kurtosis) = two_pass(&values);
+
+        assert_eq!(Some(-4.0), summary.min());
+        assert_eq!(Some(10.0), summary.max());
+        assert_close(mean, summary.mean());
+        assert_close(variance, summary.variance());
+        assert_close(skewness, summary.skewness());
+        assert_close(kurtosis, summary.kurtosis());
+    }
+
+    #[test]
+    fn stable_with_large_offset() {
+        // Summing squares would lose the variance completely here
+        let values: Vec<f64> = [4.0, 7.0, 13.0, 16.0].iter().map(|x| x + 1e9).collect();
+        let summary: Summary<f64> = values.iter().copied().collect();
+        assert_close(30.0, summary.variance());
+    }
+
+    #[test]
+    fn merge_matches_single_pass() {
+        let values: Vec<i64> = (0..100).map(|i| (i * i * 7 + 3) % 101 - 50).collect();
+        let whole: Summary<i64> = values.iter().copied().collect();
+
+        let mut left: Summary<i64> = values[..37].iter().copied().collect();
+        let right: Summary<i64> = values[37..].iter().copied().collect();
+        left.merge(&right);
+
+        assert_eq!(whole.count(), left.count());
+        assert_eq!(whole.min(), left.min());
+        assert_eq!(whole.max(), left.max());
+        assert_close(whole.sum(), Some(left.sum()));
+        assert_close(whole.mean().unwrap(), left.mean());
+        assert_close(whole.variance().unwrap(), left.variance());
+        assert_close(whole.skewness().unwrap(), left.skewness());
+        assert_close(whole.kurtosis().unwrap(), left.kurtosis());
+
+        let mut empty = Summary::new();
+        empty.merge(&whole);
+        assert_eq!(whole.count(), empty.count());
+    }
+
+    #[test]
+    fn no_spread() {
+        let summary: Summary<u8> = vec![3, 3, 3].into_iter().collect();
+        assert_close(0.0, summary.variance());
+        assert_eq!(None, summary.skewness());
+        assert_eq!(None, summary.kurtosis());
+    }
+}
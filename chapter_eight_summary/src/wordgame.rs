@@ -0,0 +1,427 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::str;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::pig_latin::{is_letter, is_vowel, keeping_case, tokenize, Dialect, Token, VowelSuffix};
+
+// Pig latin isn't the only way to play with words. Every game here turns a
+// piece of text into another and leaves anything that isn't a word alone,
+// so they can all be run over a file the same way.
+
+// A word game. Text comes in pieces that are cut just before some
+// whitespace, so a word is never split, but a game can't look past the end
+// of a piece.
+pub trait Transform {
+    fn transform(&self, text: &str) -> String;
+}
+
+impl Transform for Dialect {
+    fn transform(&self, text: &str) -> String {
+        self.to_pig_latin(text)
+    }
+}
+
+// "ub" goes before every group of vowels, even a silent "e": "hello" is
+// "hubellubo" and "apple" is "ubapplube". A "y" after the first letter
+// counts as a vowel, so "happy" is "hubappuby".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UbbiDubbi;
+
+impl Transform for UbbiDubbi {
+    fn transform(&self, text: &str) -> String {
+        words(text, |word| keeping_case(word, ubbi_dubbi))
+    }
+}
+
+fn ubbi_dubbi(word: &str) -> String {
+    let mut played = String::new();
+    let mut after_vowel = false;
+    for (i, g) in word.graphemes(true).enumerate() {
+        let vowel = is_vowel(g) || (i > 0 && (g == "y" || g == "Y"));
+        if vowel && !after_vowel {
+            played.push_str("ub");
+        }
+        played.push_str(g);
+        after_vowel = vowel;
+    }
+    played
+}
+
+// Also called Tutnese: every consonant is spelled as a syllable ("b" is
+// "bub", "c" is "cash") and vowels stay as they are. A doubled letter is
+// said once, after "squa" ("hello" is "hashesqualulo"), or "squat" for a
+// vowel ("book" is "bubsquatokuck"). Letters with accents have no syllable,
+// so they stay as they are too.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DoubleDutch;
+
+impl Transform for DoubleDutch {
+    fn transform(&self, text: &str) -> String {
+        words(text, |word| keeping_case(word, double_dutch))
+    }
+}
+
+fn double_dutch(word: &str) -> String {
+    let graphemes: Vec<&str> = word.graphemes(true).collect();
+    let mut played = String::new();
+    let mut i = 0;
+    while i < graphemes.len() {
+        let g = graphemes[i];
+        let doubled = graphemes.get(i + 1) == Some(&g) && is_letter(g);
+        if doubled {
+            played.push_str(if is_vowel(g) { "squat" } else { "squa" });
+        }
+        played.push_str(g);
+        if let Some(syllable) = syllable(g) {
+            played.push_str(syllable);
+        }
+        i += if doubled { 2 } else { 1 };
+    }
+    played
+}
+
+// What comes after a consonant to make it a syllable
+fn syllable(g: &str) -> Option<&'static str> {
+    let syllable = match g.to_ascii_lowercase().as_str() {
+        "b" => "ub",
+        "c" => "ash",
+        "d" => "ud",
+        "f" => "uf",
+        "g" => "ug",
+        "h" => "ash",
+        "j" => "ug",
+        "k" => "uck",
+        "l" => "ul",
+        "m" => "um",
+        "n" => "un",
+        "p" => "ub",
+        "q" => "uack",
+        "r" => "ug",
+        "s" => "us",
+        "t" => "ut",
+        "v" => "uv",
+        "w" => "ack",
+        "x" => "ux",
+        "y" => "ub",
+        "z" => "ub",
+        _ => return None,
+    };
+    Some(syllable)
+}
+
+// Every letter from a to z moves 13 places along the alphabet, so doing it
+// twice gives the text back. Other letters aren't touched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rot13;
+
+impl Transform for Rot13 {
+    fn transform(&self, text: &str) -> String {
+        text.chars()
+            .map(|c| match c {
+                'a'..='z' => (((c as u8 - b'a') + 13) % 26 + b'a') as char,
+                'A'..='Z' => (((c as u8 - b'A') + 13) % 26 + b'A') as char,
+                _ => c,
+            })
+            .collect()
+    }
+}
+
+// Plays `play` on every word of `text`, keeping what's between the words
+fn words<F: Fn(&str) -> String>(text: &str, play: F) -> String {
+    let mut played = String::new();
+    for token in tokenize(text) {
+        match token {
+            Token::Word(word) => played.push_str(&play(word)),
+            Token::Other(other) => played.push_str(other),
+        }
+    }
+    played
+}
+
+// How much of the input stream() reads at once
+const CHUNK: usize = 64 * 1024;
+
+// Plays `game` on everything `input` has and writes it to `output`. The
+// input is read a chunk at a time, and everything up to the last whitespace
+// in it is played straight away, so a file of any size, with or without
+// line breaks, takes about as little memory as a chunk (or its longest word).
+// Bytes that aren't UTF-8 become U+FFFD like String::from_utf8_lossy() does.
+pub fn stream<T, R, W>(game: &T, mut input: R, mut output: W) -> io::Result<()>
+where
+    T: Transform + ?Sized,
+    R: BufRead,
+    W: Write,
+{
+    let mut text = String::new();
+    // Bytes at the end of the last chunk that start a character, but don't
+    // finish it
+    let mut partial = Vec::new();
+    loop {
+        let read = match input.fill_buf() {
+            Ok(chunk) => {
+                let read = chunk.len().min(CHUNK);
+                partial.extend_from_slice(&chunk[..read]);
+                read
+            },
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if read == 0 {
+            break;
+        }
+        input.consume(read);
+        decode(&mut partial, &mut text);
+
+        // The word at the end could go on in the next chunk
+        if let Some(split) = last_boundary(&text) {
+            output.write_all(game.transform(&text[..split]).as_bytes())?;
+            text.drain(..split);
+        }
+    }
+    if !partial.is_empty() {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    output.write_all(game.transform(&text).as_bytes())?;
+    output.flush()
+}
+
+// Moves the UTF-8 in `bytes` onto the end of `text`, replacing anything
+// invalid with U+FFFD. A character cut off at the end stays in `bytes`.
+fn decode(bytes: &mut Vec<u8>, text: &mut String) {
+    let mut start = 0;
+    loop {
+        match str::from_utf8(&bytes[start..]) {
+            Ok(valid) => {
+                text.push_str(valid);
+                start = bytes.len();
+                break;
+            },
+            Err(e) => {
+                let valid_end = start + e.valid_up_to();
+                text.push_str(str::from_utf8(&bytes[start..valid_end]).unwrap());
+                match e.error_len() {
+                    Some(len) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        start = valid_end + len;
+                    },
+                    None => {
+                        start = valid_end;
+                        break;
+                    },
+                }
+            },
+        }
+    }
+    bytes.drain(..start);
+}
+
+// Where the text can be cut without splitting a word: just before its last
+// whitespace. Nothing joins onto the front of whitespace except a "\r"
+// before a "\n", so that pair is kept together. None if there's no such place.
+fn last_boundary(text: &str) -> Option<usize> {
+    let mut split = text.rfind(char::is_whitespace)?;
+    if text[split..].starts_with('\n') && text[..split].ends_with('\r') {
+        split -= 1;
+    }
+    if split > 0 {
+        Some(split)
+    } else {
+        None
+    }
+}
+
+// The games `wordgame` knows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Game {
+    PigLatin(Dialect),
+    UbbiDubbi,
+    DoubleDutch,
+    Rot13,
+}
+
+impl Transform for Game {
+    fn transform(&self, text: &str) -> String {
+        match self {
+            Game::PigLatin(dialect) => dialect.transform(text),
+            Game::UbbiDubbi => UbbiDubbi.transform(text),
+            Game::DoubleDutch => DoubleDutch.transform(text),
+            Game::Rot13 => Rot13.transform(text),
+        }
+    }
+}
+
+// What `wordgame` was asked to do
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub game: Game,
+    // None, or "-", reads stdin
+    pub path: Option<String>,
+}
+
+impl Options {
+    // `args` are the arguments after the program name:
+    //     wordgame pig-latin|ubbi-dubbi|double-dutch|rot13 [FILE] [--simple] [--suffix way|yay|hay]
+    // --simple (the book's rules, moving only the first letter) and --suffix
+    // (the ending for words starting with a vowel) are for pig latin, which
+    // otherwise moves whole consonant clusters and ends vowels with "way".
+    pub fn new(args: &[String]) -> Result<Options, &'static str> {
+        let mut iter = args.iter();
+        let name = match iter.next() {
+            Some(name) => name.as_str(),
+            None => return Err("say which game to play: pig-latin, ubbi-dubbi, double-dutch or rot13"),
+        };
+        let mut path = None;
+        let mut simple = false;
+        let mut suffix = None;
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--simple" => simple = true,
+                "--suffix" => match iter.next().map(|s| s.as_str()) {
+                    Some("way") => suffix = Some(VowelSuffix::Way),
+                    Some("yay") => suffix = Some(VowelSuffix::Yay),
+                    Some("hay") => suffix = Some(VowelSuffix::Hay),
+                    _ => return Err("--suffix needs way, yay or hay"),
+                },
+                _ if path.is_none() => path = Some(arg.clone()),
+                _ => return Err("wordgame reads only one file, use - for stdin"),
+            }
+        }
+
+        let game = match name {
+            "pig-latin" => {
+                let mut dialect = if simple { Dialect::simple() } else { Dialect::standard(VowelSuffix::Way) };
+                if let Some(suffix) = suffix {
+                    dialect.vowel_suffix = suffix;
+                }
+                Game::PigLatin(dialect)
+            },
+            _ if simple || suffix.is_some() => return Err("--simple and --suffix are only for pig-latin"),
+            "ubbi-dubbi" => Game::UbbiDubbi,
+            "double-dutch" => Game::DoubleDutch,
+            "rot13" => Game::Rot13,
+            _ => return Err("the games are pig-latin, ubbi-dubbi, double-dutch and rot13"),
+        };
+
+        if path.as_deref() == Some("-") {
+            path = None;
+        }
+        Ok(Options { game, path })
+    }
+}
+
+// Plays the game on the file (or stdin) and writes the result to stdout
+pub fn run(options: &Options) -> Result<(), Box<dyn Error>> {
+    let stdout = io::stdout();
+    let output = BufWriter::new(stdout.lock());
+    match &options.path {
+        Some(path) => stream(&options.game, BufReader::new(File::open(path)?), output)?,
+        None => stream(&options.game, io::stdin().lock(), output)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn ubbi_dubbi_words() {
+        assert_eq!("Hubellubo, wuborld!", UbbiDubbi.transform("Hello, world!"));
+        assert_eq!("Ubapplube yubou hubappuby", UbbiDubbi.transform("Apple you happy"));
+        assert_eq!("RUBOT cubafubé 東京", UbbiDubbi.transform("ROT café 東京"));
+    }
+
+    #[test]
+    fn double_dutch_words() {
+        assert_eq!("hashesqualulo bubsquatokuck", DoubleDutch.transform("hello book"));
+        assert_eq!("Cashatut, DUDOGUG!", DoubleDutch.transform("Cat, DOG!"));
+        // Mixed case is played as it is
+        assert_eq!("iPubhashonune", DoubleDutch.transform("iPhone"));
+        assert_eq!("ça é", DoubleDutch.transform("ça é"));
+    }
+
+    #[test]
+    fn rot13() {
+        assert_eq!("Uryyb, Jbeyq! 42 éß", Rot13.transform("Hello, World! 42 éß"));
+        assert_eq!("nopqrstuvwxyzabcdefghijklm", Rot13.transform("abcdefghijklmnopqrstuvwxyz"));
+    }
+
+    #[test]
+    fn streams_every_line() {
+        let mut output = Vec::new();
+        stream(&Rot13, "Hello\nworld\r\n\nlast".as_bytes(), &mut output).unwrap();
+        assert_eq!("Uryyb\njbeyq\r\n\nynfg", String::from_utf8(output).unwrap());
+
+        let mut output = Vec::new();
+        let game: &dyn Transform = &Dialect::standard(VowelSuffix::Way);
+        stream(game, "string\napple\n".as_bytes(), &mut output).unwrap();
+        assert_eq!("ing-stray\napple-way\n", String::from_utf8(output).unwrap());
+
+    }
+
+    #[test]
+    fn streams_in_small_chunks() {
+        // No line breaks, characters cut in half and words cut in two by the
+        // chunks all come out the same as playing the whole text at once
+        let text = "Hello, wörld!\r\nThe quick brown fox jumps over the lazy dog. Ça va?";
+        let game = Dialect::standard(VowelSuffix::Way);
+        for size in 1..10 {
+            let mut output = Vec::new();
+            stream(&game, BufReader::with_capacity(size, text.as_bytes()), &mut output).unwrap();
+            assert_eq!(game.transform(text), String::from_utf8(output).unwrap());
+        }
+    }
+
+    #[test]
+    fn streams_bytes_that_are_not_utf8() {
+        let mut output = Vec::new();
+        stream(&Rot13, &b"ab\xffcd\n\xc3"[..], &mut output).unwrap();
+        assert_eq!("no\u{FFFD}pq\n\u{FFFD}", String::from_utf8(output).unwrap());
+
+        // "é" is 0xc3 0xa9; cut after 0xc3 it's still one "é"
+        let mut output = Vec::new();
+        stream(&Rot13, BufReader::with_capacity(1, &b"\xc3\xa9\xc3x"[..]), &mut output).unwrap();
+        assert_eq!("é\u{FFFD}k", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn parse_options() {
+        assert_eq!(
+            Options { game: Game::PigLatin(Dialect::standard(VowelSuffix::Way)), path: Some(String::from("book.txt")) },
+            Options::new(&args(&["pig-latin", "book.txt"])).unwrap()
+        );
+        assert_eq!(
+            Options { game: Game::PigLatin(Dialect { vowel_suffix: VowelSuffix::Yay, ..Dialect::simple() }), path: None },
+            Options::new(&args(&["pig-latin", "--simple", "-", "--suffix", "yay"])).unwrap()
+        );
+        assert_eq!(Game::Rot13, Options::new(&args(&["rot13"])).unwrap().game);
+        assert!(Options::new(&args(&[])).is_err());
+        assert!(Options::new(&args(&["klingon"])).is_err());
+        assert!(Options::new(&args(&["rot13", "--simple"])).is_err());
+        assert!(Options::new(&args(&["pig-latin", "--suffix", "bay"])).is_err());
+        assert!(Options::new(&args(&["rot13", "a.txt", "b.txt"])).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn rot13_twice_is_nothing(text in "\\PC*") {
+            prop_assert_eq!(&text, &Rot13.transform(&Rot13.transform(&text)));
+        }
+
+        // Only words change, so what's between them is still there
+        #[test]
+        fn games_keep_what_is_between_words(text in "[ ,.!?\\n0-9]{0,20}") {
+            prop_assert_eq!(&text, &UbbiDubbi.transform(&text));
+            prop_assert_eq!(&text, &DoubleDutch.transform(&text));
+        }
+    }
+}
@@ -1,141 +1,125 @@
-use std::io;
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+use prompt::{Prompt, PromptError};
 
 fn main() {
-
-    let mut keep_going = true;
+    let mut prompt = Prompt::stdio();
 
     println!("\nFahrenheit to Celsius");
-    loop {
-        let mut user_input = String::new();
-        println!("Input temperature in fahrenheit:");
-        io::stdin().read_line(&mut user_input).expect("Failed to read line.");
-
-        let trimmed = user_input.trim();
-
-        if trimmed == "next" || trimmed == "continue" {
-            break;
-        } else if trimmed == "exit" || trimmed == "quit" {
-            keep_going = false;
-            break;
-        }
-
-        let fahrenheit: f64 = match trimmed.parse() {
-            Ok(num) => num,
-            Err(_) => continue
-        };
-
+    let mut keep_going = repeat(&mut prompt, "Input temperature in fahrenheit:", any, |fahrenheit: f64| {
         // I have gone through this before and know about ownership
         // see chapter 4 to understand why it's &fahrenheit not fahrenheit
         let celsius = fahrenheit_to_celsius(&fahrenheit);
         println!("Temperature in celsisus: {} degrees", celsius);
-    }
+    });
 
     if keep_going {
         println!("\nCelsius to Fahrenheit");
-        loop {
-            let mut user_input = String::new();
-            println!("Input temperature in celsius:");
-            io::stdin().read_line(&mut user_input).expect("Failed to read line.");
-
-            let trimmed = user_input.trim();
-
-            if trimmed == "next" || trimmed == "continue" {
-                break;
-            } else if trimmed == "exit" || trimmed == "quit" {
-                keep_going = false;
-                break;
-            }
-
-            let celsius: f64 = match trimmed.parse() {
-                Ok(num) => num,
-                Err(_) => continue,
-            };
-
+        keep_going = repeat(&mut prompt, "Input temperature in celsius:", any, |celsius: f64| {
             // I have gone through this before and know about ownership
             // see chapter 4 to understand why it's &celsius not celsius
             let fahrenheit = celsius_to_fahrenheit(&celsius);
             println!("Temperature in fahrenheit: {} degrees", fahrenheit);
-        }
+        });
     }
 
     if keep_going {
         println!("\nFind the nth Fibonacci number");
-        loop {
-            let mut user_input = String::new();
-            println!("Input which fibonacci number you'd like (what n):");
-
-            io::stdin().read_line(&mut user_input).expect("Failed to read line.");
-
-            let trimmed = user_input.trim();
-
-            if trimmed == "next" || trimmed == "continue" {
-                break;
-            } else if trimmed == "exit" || trimmed == "quit" {
-                keep_going = false;
-                break;
-            }
-
-            let n: u32 = match trimmed.parse() {
-                Ok(num) => num,
-                Err(_) => continue,
-            };
-
+        keep_going = repeat(&mut prompt, "Input which fibonacci number you'd like (what n):", fits_in_u128, |n: u32| {
             let fib = nth_fibonacci_number(n);
             println!("That Fibonacci number is: {}", fib);
-        }
+        });
     }
 
     if keep_going {
         println!("\nThe 12 days of Christmas");
-        loop {
-            let mut user_input = String::new();
-
-            println!("Input the day you would like to know what gifts my true love gave to me:");
-
-            io::stdin().read_line(&mut user_input).expect("Failed to read line.");
-
-            let trimmed = user_input.trim();
-
-            if trimmed == "next" || trimmed == "continue" {
-                break;
-            } else if trimmed == "exit" || trimmed == "quit" {
-                keep_going = false;
-                break;
-            }
-
-            let day = match trimmed.parse() {
-                Ok(num) => num,
-                Err(_) => continue,
-            };
-
+        keep_going = repeat(&mut prompt, "Input the day you would like to know what gifts my true love gave to me:", one_of_twelve, |day: usize| {
             twelve_days_gifts(&day);
-        }
+        });
     }
 
     if keep_going {
         println!("\nCummulative Presents From 12 Days of Christmas");
-        loop {
-            let mut user_input = String::new();
+        repeat(&mut prompt, "Please input the day of Christmas to see cummulative presents up to then:", one_of_twelve, |day: usize| {
+            cummulative_twelve_days(&day);
+        });
+    }
+}
 
-            println!("Please input the day of Christmas to see cummulative presents up to then:");
+// What the user typed in one of the exercises: a value to work with, or a
+// word to move on to the next exercise or stop altogether
+enum Input<T> {
+    Value(T),
+    Next,
+    Quit,
+}
 
-            io::stdin().read_line(&mut user_input).expect("Failed to read line.");
+impl<T: FromStr> FromStr for Input<T>
+where
+    T::Err: Display,
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Input<T>, String> {
+        match s {
+            "next" | "continue" => Ok(Input::Next),
+            "exit" | "quit" => Ok(Input::Quit),
+            _ => match s.parse() {
+                Ok(value) => Ok(Input::Value(value)),
+                Err(e) => Err(format!("{} (type next to move on or quit to stop)", e)),
+            },
+        }
+    }
+}
 
-            let trimmed = user_input.trim();
+// Keeps asking for values that pass `validate` and handing them to
+// `handle`, until the user types next (true: go on to the next exercise)
+// or quit (false: stop). The end of the input stops as well.
+fn repeat<R, W, T, V, F>(prompt: &mut Prompt<R, W>, message: &str, validate: V, mut handle: F) -> bool
+where
+    R: BufRead,
+    W: Write,
+    T: FromStr,
+    T::Err: Display,
+    V: Fn(&T) -> Result<(), String>,
+    F: FnMut(T),
+{
+    loop {
+        let input = prompt.ask_with(message, |input: &Input<T>| match input {
+            Input::Value(value) => validate(value),
+            _ => Ok(()),
+        });
+        match input {
+            Ok(Input::Value(value)) => handle(value),
+            Ok(Input::Next) => return true,
+            Ok(Input::Quit) | Err(PromptError::Eof) => return false,
+            Err(e) => {
+                eprintln!("Problem reading the input: {}", e);
+                return false;
+            },
+        }
+    }
+}
 
-            if trimmed == "next" || trimmed == "continue" {
-                break;
-            } else if trimmed == "exit" || trimmed == "quit" {
-                break;
-            }
+fn any<T>(_: &T) -> Result<(), String> {
+    Ok(())
+}
 
-            let day = match trimmed.parse() {
-                Ok(num) => num,
-                Err(_) => continue,
-            };
+// Past the 186th, Fibonacci numbers are too big for a u128
+fn fits_in_u128(n: &u32) -> Result<(), String> {
+    if *n <= 186 {
+        Ok(())
+    } else {
+        Err(String::from("n has to be at most 186, the numbers after that are too big"))
+    }
+}
 
-            cummulative_twelve_days(&day);
-        }
+fn one_of_twelve(day: &usize) -> Result<(), String> {
+    if *day >= 1 && *day <= 12 {
+        Ok(())
+    } else {
+        Err(String::from("There are only 12 days of Christmas, from 1 to 12"))
     }
 }
 
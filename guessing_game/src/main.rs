@@ -1,6 +1,27 @@
-use std::io;
 use std::cmp::Ordering;
+use std::str::FromStr;
 use rand::Rng;
+use prompt::{Prompt, PromptError};
+
+// What the player typed: a guess, or that they've had enough
+enum Guess {
+    Number(u32),
+    Quit,
+}
+
+impl FromStr for Guess {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Guess, &'static str> {
+        if s == "quit" {
+            return Ok(Guess::Quit);
+        }
+        match s.parse() {
+            Ok(num) => Ok(Guess::Number(num)),
+            Err(_) => Err("guess a whole number, or type quit to give up"),
+        }
+    }
+}
 
 fn main() {
     println!("Guess the number!");
@@ -8,33 +29,30 @@ fn main() {
     let secret_number = rand::thread_rng().gen_range(1,101);
 
 
+    // The prompt keeps asking until it gets a number from 1 to 100 (or
+    // "quit"), saying what was wrong with anything else
+    let mut prompt = Prompt::stdio();
+
     // This makes an infinite loop
     loop {
-        println!("Please input your guess.");
-
-        // let is how variables are initialized
-        // mut means the variable is mutable (can be changed)
-        let mut guess = String::new();
-
-        // Reading in a line from the terminal
-        // the .expect() is for handling potential failure
-        io::stdin().read_line(&mut guess).expect("Failed to read line");
-
+        let guess = prompt.ask_with("Please input your guess.", |guess: &Guess| match guess {
+            Guess::Number(n) if *n < 1 || *n > 100 => Err(String::from("The secret number is between 1 and 100.")),
+            _ => Ok(()),
+        });
 
         // Not in book (chapter 2) but wanted to try adding it
-        // The .trim() removes white spaces at the start and end of the string
-        if guess.trim() == "quit" {
-            println!("The secret number was {}. Better luck next time!", secret_number);
-            break;
-        }
-
-        // This guess variable is shadowing the previous guess variable
-        let guess: u32 = match guess.trim().parse() {
-            Ok(num) => num,
-            Err(_) => continue,
+        // Typing quit (or closing the input) gives up
+        let guess = match guess {
+            Ok(Guess::Number(num)) => num,
+            Ok(Guess::Quit) | Err(PromptError::Eof) => {
+                println!("The secret number was {}. Better luck next time!", secret_number);
+                break;
+            },
+            Err(e) => {
+                eprintln!("Problem reading your guess: {}", e);
+                break;
+            },
         };
-        // The above is now a match expression. If the guess.trim().parse() works
-        // Ok, then the number is assigned. If there is an Error, continue (start the loop again)
 
         // Printing a string and using a placeholder
         println!("You guessed: {}", guess);
@@ -0,0 +1,122 @@
+// Compares the skip-table search in minigrep::search with the original
+// implementation, which split the contents with lines() and called
+// contains on every line.
+//
+// Run with `cargo bench`. MINIGREP_BENCH_MB sets the size of the large
+// synthetic corpus (64 MB by default).
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// The original search from the Rust book, kept here as the baseline
+fn search_lines<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
+    let mut results = Vec::new();
+    for line in contents.lines() {
+        if line.contains(query) {
+            results.push(line);
+        }
+    }
+    results
+}
+
+fn search_lines_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
+    let query = query.to_lowercase();
+    let mut results = Vec::new();
+    for line in contents.lines() {
+        if line.to_lowercase().contains(&query) {
+            results.push(line);
+        }
+    }
+    results
+}
+
+// English-looking text made from a fixed word list. A small linear
+// congruential generator keeps the corpus the same on every run.
+fn synthetic_corpus(bytes: usize, needle: &str, every: usize) -> String {
+    let words = [
+        "the", "of", "and", "to", "in", "a", "is", "that", "for", "it", "as", "was", "with",
+        "be", "by", "on", "not", "he", "this", "are", "or", "his", "from", "at", "which",
+        "but", "have", "an", "had", "they", "you", "were", "their", "one", "all", "we",
+        "nobody", "somebody", "frog", "bog", "livelong", "admiring", "dreary", "public",
+    ];
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) as usize
+    };
+
+    let mut corpus = String::with_capacity(bytes + 128);
+    let mut line = 0;
+    while corpus.len() < bytes {
+        let length = 4 + next() % 12;
+        for i in 0..length {
+            if i > 0 {
+                corpus.push(' ');
+            }
+            corpus.push_str(words[next() % words.len()]);
+        }
+        line += 1;
+        if line % every == 0 {
+            corpus.push(' ');
+            corpus.push_str(needle);
+        }
+        corpus.push('\n');
+    }
+    corpus
+}
+
+// Runs `f` for at least half a second and reports the average time and
+// throughput of one run
+fn bench<F: FnMut() -> usize>(name: &str, bytes: usize, mut f: F) {
+    let mut runs = 0;
+    let mut found = 0;
+    let start = Instant::now();
+    while runs == 0 || start.elapsed() < Duration::from_millis(500) {
+        found = black_box(f());
+        runs += 1;
+    }
+    let per_run = start.elapsed() / runs;
+    let throughput = bytes as f64 / per_run.as_secs_f64() / (1024.0 * 1024.0);
+
+    println!("{:<58} {:>12.3?} {:>10.1} MB/s {:>8} lines", name, per_run, throughput, found);
+}
+
+fn compare(corpus_name: &str, query: &str, contents: &str) {
+    assert_eq!(search_lines(query, contents), minigrep::search(query, contents));
+    assert_eq!(
+        search_lines_case_insensitive(query, contents),
+        minigrep::search_case_insensitive(query, contents)
+    );
+
+    let bytes = contents.len();
+    bench(&format!("{} {:?} lines+contains", corpus_name, query), bytes, || {
+        search_lines(black_box(query), black_box(contents)).len()
+    });
+    bench(&format!("{} {:?} skip table", corpus_name, query), bytes, || {
+        minigrep::search(black_box(query), black_box(contents)).len()
+    });
+    bench(&format!("{} {:?} lines+to_lowercase (-i)", corpus_name, query), bytes, || {
+        search_lines_case_insensitive(black_box(query), black_box(contents)).len()
+    });
+    bench(&format!("{} {:?} skip table (-i)", corpus_name, query), bytes, || {
+        minigrep::search_case_insensitive(black_box(query), black_box(contents)).len()
+    });
+}
+
+fn main() {
+    let poem = fs::read_to_string("poem.txt").expect("run the benchmarks from the minigrep directory");
+    compare("poem.txt", "the", &poem);
+    compare("poem.txt", "nobody", &poem);
+
+    // poem.txt repeated until it is about a megabyte
+    let poems = poem.repeat(1024 * 1024 / poem.len());
+    compare("poem.txt x 4766", "livelong", &poems);
+
+    let megabytes: usize = env::var("MINIGREP_BENCH_MB").ok().and_then(|mb| mb.parse().ok()).unwrap_or(64);
+    let corpus = synthetic_corpus(megabytes * 1024 * 1024, "Sherlock Holmes", 10_000);
+    let name = format!("synthetic {} MB", megabytes);
+    compare(&name, "Sherlock Holmes", &corpus);
+    compare(&name, "frog", &corpus);
+    compare(&name, "zebra crossing", &corpus);
+}
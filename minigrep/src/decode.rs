@@ -0,0 +1,122 @@
+use std::io::{self, Read};
+
+use encoding_rs::{Decoder, Encoding};
+
+// How many encoded bytes we decode at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Wraps a reader of text in some other encoding (UTF-16, Windows-1252, ...)
+// and gives back the same text as UTF-8, one chunk at a time, so a large
+// file never has to be converted all at once.
+//
+// A byte order mark at the start of the input wins over the encoding we
+// were given and is removed, which is how browsers decode text as well.
+// Bytes that aren't valid in the encoding become U+FFFD.
+pub struct DecodeReader<R: Read> {
+    inner: R,
+    decoder: Decoder,
+    input: Vec<u8>,
+    // Decoded text that hasn't been read yet starts at output[pos]
+    output: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> DecodeReader<R> {
+    pub fn new(inner: R, encoding: &'static Encoding) -> DecodeReader<R> {
+        DecodeReader {
+            inner,
+            decoder: encoding.new_decoder(),
+            input: vec![0; CHUNK_SIZE],
+            output: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let read = loop {
+            match self.inner.read(&mut self.input) {
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+        let last = read == 0;
+
+        // With room for the worst case the decoder always takes all of
+        // the input in one call
+        let room = self.decoder.max_utf8_buffer_length(read).unwrap_or(read * 3 + 16);
+        self.output.resize(room, 0);
+        let (_, consumed, written, _) = self.decoder.decode_to_utf8(&self.input[..read], &mut self.output, last);
+        debug_assert_eq!(read, consumed);
+
+        self.output.truncate(written);
+        self.pos = 0;
+        self.done = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecodeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // A chunk can decode to nothing, e.g. half of a UTF-16 character
+        while self.pos == self.output.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+
+        let count = buf.len().min(self.output.len() - self.pos);
+        buf[..count].copy_from_slice(&self.output[self.pos..self.pos + count]);
+        self.pos += count;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+    fn decode(bytes: &[u8], encoding: &'static Encoding) -> String {
+        let mut text = String::new();
+        DecodeReader::new(bytes, encoding).read_to_string(&mut text).unwrap();
+        text
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|unit| unit.to_le_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn decodes_windows_1252() {
+        assert_eq!("café – 10€", decode(b"caf\xe9 \x96 10\x80", WINDOWS_1252));
+    }
+
+    #[test]
+    fn decodes_utf16_and_removes_bom() {
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(utf16le("über\r\nalles"));
+        assert_eq!("über\r\nalles", decode(&bytes, UTF_16LE));
+
+        // The BOM wins over the encoding we were told to use
+        assert_eq!("über\r\nalles", decode(&bytes, WINDOWS_1252));
+
+        let big_endian: Vec<u8> = "hi".encode_utf16().flat_map(|unit| unit.to_be_bytes().to_vec()).collect();
+        assert_eq!("hi", decode(&big_endian, UTF_16BE));
+    }
+
+    #[test]
+    fn characters_split_across_chunks() {
+        // Every 'ü' is two bytes in UTF-16 and the chunk boundary falls
+        // in the middle of the text, so some characters are cut in half
+        let text = "ü".repeat(CHUNK_SIZE);
+        assert_eq!(text, decode(&utf16le(&text), UTF_16LE));
+    }
+
+    #[test]
+    fn invalid_bytes_are_replaced() {
+        assert_eq!("a\u{fffd}b", decode(b"a\xffb", UTF_8));
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use crate::sink::Sink;
+
+// One piece of a --format template
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Literal(String),
+    Path,
+    Line,
+    Column,
+    Text,
+}
+
+// An output template such as '{path}:{line}:{col}: {text}'. The
+// placeholders are:
+//     {path}  the file the match is in
+//     {line}  the line number, starting at 1
+//     {col}   the byte column of the match, starting at 1
+//     {text}  the matching line, or just the match with -o
+// Use {{ and }} for literal braces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, &'static str> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('{') if name.is_empty() => {
+                                literal.push('{');
+                                break;
+                            },
+                            Some('}') => {
+                                if !literal.is_empty() {
+                                    pieces.push(Piece::Literal(literal.clone()));
+                                    literal.clear();
+                                }
+                                pieces.push(match name.as_str() {
+                                    "path" => Piece::Path,
+                                    "line" => Piece::Line,
+                                    "col" => Piece::Column,
+                                    "text" => Piece::Text,
+                                    _ => return Err("unknown placeholder in --format, use {path}, {line}, {col} or {text}"),
+                                });
+                                break;
+                            },
+                            Some(c) => name.push(c),
+                            None => return Err("unclosed '{' in --format, use {{ for a literal brace"),
+                        }
+                    }
+                },
+                '}' => match chars.next() {
+                    Some('}') => literal.push('}'),
+                    _ => return Err("unmatched '}' in --format, use }} for a literal brace"),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+
+        Ok(Template { pieces })
+    }
+
+    fn write<W: Write>(&self, wtr: &mut W, path: &str, line: usize, column: usize, text: &str) -> io::Result<()> {
+        for piece in self.pieces.iter() {
+            match piece {
+                Piece::Literal(literal) => wtr.write_all(literal.as_bytes())?,
+                Piece::Path => wtr.write_all(path.as_bytes())?,
+                Piece::Line => write!(wtr, "{}", line)?,
+                Piece::Column => write!(wtr, "{}", column)?,
+                Piece::Text => wtr.write_all(text.as_bytes())?,
+            }
+        }
+        writeln!(wtr)
+    }
+}
+
+// Prints every match through a Template. Context lines are left out since
+// templates are meant for feeding other tools, not for reading.
+pub struct TemplateSink<W: Write> {
+    wtr: W,
+    template: Template,
+    path: String,
+    only_matching: bool,
+}
+
+impl<W: Write> TemplateSink<W> {
+    // `path` is what {path} shows until begin() reports another file
+    pub fn new(wtr: W, template: Template, path: &str) -> TemplateSink<W> {
+        TemplateSink {
+            wtr,
+            template,
+            path: path.to_string(),
+            only_matching: false,
+        }
+    }
+
+    // Use the template once per match instead of once per line
+    pub fn only_matching(mut self) -> TemplateSink<W> {
+        self.only_matching = true;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.wtr
+    }
+}
+
+impl<W: Write> Sink for TemplateSink<W> {
+    fn begin(&mut self, path: &Path) -> io::Result<()> {
+        self.path = path.display().to_string();
+        Ok(())
+    }
+
+    fn matched(&mut self, line_number: usize, line: &str, matches: &[Range<usize>]) -> io::Result<()> {
+        if !self.only_matching {
+            let column = matches.first().map_or(1, |range| range.start + 1);
+            return self.template.write(&mut self.wtr, &self.path, line_number, column, line);
+        }
+        for range in matches {
+            self.template.write(&mut self.wtr, &self.path, line_number, range.start + 1, &line[range.clone()])?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.wtr.flush()
+    }
+}
+
+#[cfg(test)]
+// Single-match lines are written as &[start..end], which is what we mean
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_placeholders_and_braces() {
+        let template = Template::parse("{path}:{line}:{col}: {{{text}}}").unwrap();
+        assert_eq!(
+            vec![
+                Piece::Path,
+                Piece::Literal(String::from(":")),
+                Piece::Line,
+                Piece::Literal(String::from(":")),
+                Piece::Column,
+                Piece::Literal(String::from(": {")),
+                Piece::Text,
+                Piece::Literal(String::from("}")),
+            ],
+            template.pieces
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Template::parse("{name}").is_err());
+        assert!(Template::parse("{path").is_err());
+        assert!(Template::parse("path}").is_err());
+    }
+
+    #[test]
+    fn whole_lines() {
+        let template = Template::parse("{path}:{line}:{col}: {text}").unwrap();
+        let mut sink = TemplateSink::new(Vec::new(), template, "poem.txt");
+        sink.context(1, "ignored").unwrap();
+        sink.matched(2, "Are you nobody, too?", &[8..14]).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("poem.txt:2:9: Are you nobody, too?\n", output);
+    }
+
+    #[test]
+    fn only_matching() {
+        let template = Template::parse("{line}:{col} {text}").unwrap();
+        let mut sink = TemplateSink::new(Vec::new(), template, "log").only_matching();
+        sink.begin(Path::new("access.log")).unwrap();
+        sink.matched(3, "id=7 id=42", &[3..4, 8..10]).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("3:4 7\n3:9 42\n", output);
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::walk;
+
+// `minigrep index build DIR` writes the index into DIR under this name
+pub const INDEX_FILE_NAME: &str = ".minigrep-index";
+
+// Start of every index file, with a version number in case the format changes
+const MAGIC: &[u8; 8] = b"MGIDX01\n";
+
+// A trigram index remembers, for every run of three bytes, which files
+// contain it. A file can only contain "frog" if it contains both "fro" and
+// "rog", so intersecting those two lists gives the only files worth
+// searching. The real search still runs on those files, the index just
+// saves us from opening the rest.
+//
+// Trigrams are stored with ASCII letters lowercased, so one index works
+// for both case-sensitive and case-insensitive searches.
+pub struct Index {
+    root: PathBuf,
+    files: Vec<IndexedFile>,
+    ids: HashMap<PathBuf, u32>,
+    // Trigram (packed into the low 24 bits) -> ids of the files containing it
+    postings: HashMap<u32, Vec<u32>>,
+}
+
+// What a file looked like when it was indexed. If its size or modification
+// time has changed since, the index can't be trusted for it.
+struct IndexedFile {
+    path: PathBuf,
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+}
+
+impl IndexedFile {
+    fn new(path: PathBuf, metadata: &Metadata) -> IndexedFile {
+        let (modified_secs, modified_nanos) = modified_time(metadata);
+        IndexedFile {
+            path,
+            size: metadata.len(),
+            modified_secs,
+            modified_nanos,
+        }
+    }
+
+    fn is_stale(&self, metadata: &Metadata) -> bool {
+        self.size != metadata.len() || (self.modified_secs, self.modified_nanos) != modified_time(metadata)
+    }
+}
+
+impl Index {
+    // Reads every file under `root` and records its trigrams
+    pub fn build(root: &Path) -> io::Result<Index> {
+        let mut index = Index {
+            root: root.to_path_buf(),
+            files: Vec::new(),
+            ids: HashMap::new(),
+            postings: HashMap::new(),
+        };
+
+        walk::visit_files(root, &mut |path| {
+            if is_index_file(path) {
+                return Ok(());
+            }
+            let metadata = fs::metadata(path)?;
+            let contents = fs::read(path)?;
+
+            let id = index.files.len() as u32;
+            let relative = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+            index.ids.insert(relative.clone(), id);
+            index.files.push(IndexedFile::new(relative, &metadata));
+
+            for trigram in trigrams(&contents) {
+                index.postings.entry(trigram).or_default().push(id);
+            }
+            Ok(())
+        })?;
+
+        Ok(index)
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    // Where the index for `root` lives on disk
+    pub fn path_in(root: &Path) -> PathBuf {
+        root.join(INDEX_FILE_NAME)
+    }
+
+    pub fn load(root: &Path) -> io::Result<Index> {
+        let file = File::open(Index::path_in(root)).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("no index in {} ({}), run `minigrep index build {}` first", root.display(), e, root.display()),
+            )
+        })?;
+        // The lengths and counts in the file are only trusted as far as
+        // there are bytes left to back them up, so a corrupt index can't
+        // make us allocate more than the file holds
+        let size = file.metadata()?.len();
+        let mut reader = BufReader::new(file).take(size);
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a minigrep index"));
+        }
+
+        let mut index = Index {
+            root: root.to_path_buf(),
+            files: Vec::new(),
+            ids: HashMap::new(),
+            postings: HashMap::new(),
+        };
+
+        let file_count = read_u32(&mut reader)?;
+        for id in 0..file_count {
+            let length = read_u32(&mut reader)?;
+            if u64::from(length) > reader.limit() {
+                return Err(invalid("index has a path longer than the file"));
+            }
+            let mut bytes = vec![0; length as usize];
+            reader.read_exact(&mut bytes)?;
+            let path = PathBuf::from(String::from_utf8(bytes).map_err(|_| invalid("index has a path that isn't UTF-8"))?);
+
+            let size = read_u64(&mut reader)?;
+            let modified_secs = read_u64(&mut reader)?;
+            let modified_nanos = read_u32(&mut reader)?;
+
+            index.ids.insert(path.clone(), id);
+            index.files.push(IndexedFile { path, size, modified_secs, modified_nanos });
+        }
+
+        let trigram_count = read_u32(&mut reader)?;
+        for _ in 0..trigram_count {
+            let trigram = read_u32(&mut reader)?;
+            let count = read_u32(&mut reader)?;
+            if u64::from(count) * 4 > reader.limit() {
+                return Err(invalid("index has more file ids than the file holds"));
+            }
+            let mut ids = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let id = read_u32(&mut reader)?;
+                if id >= file_count {
+                    return Err(invalid("index refers to a file it doesn't list"));
+                }
+                ids.push(id);
+            }
+            index.postings.insert(trigram, ids);
+        }
+
+        Ok(index)
+    }
+
+    // The file format is just numbers in little-endian order:
+    //     magic, file count, then per file: path length, path, size,
+    //     modified seconds, modified nanoseconds,
+    //     trigram count, then per trigram: trigram, id count, ids
+    pub fn save(&self) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(Index::path_in(&self.root))?);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.files.len() as u32).to_le_bytes())?;
+        for file in self.files.iter() {
+            let path = file.path.to_str().ok_or_else(|| invalid("can't index a path that isn't UTF-8"))?;
+            writer.write_all(&(path.len() as u32).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&file.size.to_le_bytes())?;
+            writer.write_all(&file.modified_secs.to_le_bytes())?;
+            writer.write_all(&file.modified_nanos.to_le_bytes())?;
+        }
+
+        // Sorted so the file doesn't depend on the HashMap's random order
+        let mut trigrams: Vec<&u32> = self.postings.keys().collect();
+        trigrams.sort();
+        writer.write_all(&(trigrams.len() as u32).to_le_bytes())?;
+        for trigram in trigrams {
+            let ids = &self.postings[trigram];
+            writer.write_all(&trigram.to_le_bytes())?;
+            writer.write_all(&(ids.len() as u32).to_le_bytes())?;
+            for id in ids {
+                writer.write_all(&id.to_le_bytes())?;
+            }
+        }
+
+        writer.flush()
+    }
+
+    // Narrows the index down to the files that could contain `query`
+    pub fn candidates(self, query: &str, case_sensitive: bool) -> Candidates {
+        // Case-insensitive Unicode matching can turn one byte sequence into
+        // another, and queries under three bytes have no trigrams. The index
+        // can't rule anything out for those.
+        let ids = if (!case_sensitive && !query.is_ascii()) || query.len() < 3 {
+            None
+        } else {
+            let mut ids: Option<HashSet<u32>> = None;
+            for trigram in trigrams(query.as_bytes()) {
+                let containing: HashSet<u32> = match self.postings.get(&trigram) {
+                    Some(list) => list.iter().copied().collect(),
+                    None => HashSet::new(),
+                };
+                ids = Some(match ids {
+                    Some(ids) => ids.intersection(&containing).copied().collect(),
+                    None => containing,
+                });
+            }
+            ids
+        };
+
+        Candidates { index: self, ids }
+    }
+}
+
+// The result of looking a query up in the index
+pub struct Candidates {
+    index: Index,
+    // None means every indexed file might match
+    ids: Option<HashSet<u32>>,
+}
+
+impl Candidates {
+    // True unless the index is sure the file doesn't contain the query.
+    // Files added or changed since the index was built are always searched.
+    pub fn might_match(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.index.root).unwrap_or(path);
+        let id = match self.index.ids.get(relative) {
+            Some(id) => *id,
+            None => return true,
+        };
+
+        match fs::metadata(path) {
+            Ok(metadata) if !self.index.files[id as usize].is_stale(&metadata) => {},
+            _ => return true,
+        }
+
+        match &self.ids {
+            Some(ids) => ids.contains(&id),
+            None => true,
+        }
+    }
+}
+
+pub fn is_index_file(path: &Path) -> bool {
+    path.file_name() == Some(OsStr::new(INDEX_FILE_NAME))
+}
+
+// Every distinct run of three bytes in `bytes`, lowercased. Runs that
+// cross a newline are left out because a match never spans two lines.
+fn trigrams(bytes: &[u8]) -> HashSet<u32> {
+    bytes
+        .windows(3)
+        .filter(|w| !w.contains(&b'\n'))
+        .map(|w| {
+            let w = [w[0].to_ascii_lowercase(), w[1].to_ascii_lowercase(), w[2].to_ascii_lowercase()];
+            u32::from(w[0]) << 16 | u32::from(w[1]) << 8 | u32::from(w[2])
+        })
+        .collect()
+}
+
+fn modified_time(metadata: &Metadata) -> (u64, u32) {
+    match metadata.modified().ok().and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+        Some(since) => (since.as_secs(), since.subsec_nanos()),
+        None => (0, 0),
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("minigrep-index-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn candidate_names(candidates: &Candidates, dir: &Path, names: &[&str]) -> Vec<String> {
+        names
+            .iter()
+            .filter(|name| candidates.might_match(&dir.join(name)))
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn trigrams_are_lowercased_per_line() {
+        let expected: HashSet<u32> = [0x0061_6263, 0x0062_6364].iter().copied().collect();
+        assert_eq!(expected, trigrams(b"aBcd\nxy"));
+    }
+
+    #[test]
+    fn narrows_candidates_and_survives_a_round_trip() {
+        let dir = temp_dir("round-trip");
+        fs::write(dir.join("frog.txt"), "How public, like a frog").unwrap();
+        fs::write(dir.join("bog.txt"), "To an admiring bog!").unwrap();
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/both.txt"), "a FROG in a bog").unwrap();
+
+        let index = Index::build(&dir).unwrap();
+        assert_eq!(3, index.file_count());
+        index.save().unwrap();
+
+        let names = ["frog.txt", "bog.txt", "sub/both.txt"];
+        let frog = Index::load(&dir).unwrap().candidates("frog", true);
+        let bog = Index::load(&dir).unwrap().candidates("BOG", false);
+        let zebra = Index::load(&dir).unwrap().candidates("zebra", true);
+        let short = Index::load(&dir).unwrap().candidates("a", true);
+
+        assert_eq!(vec!["frog.txt", "sub/both.txt"], candidate_names(&frog, &dir, &names));
+        assert_eq!(vec!["bog.txt", "sub/both.txt"], candidate_names(&bog, &dir, &names));
+        assert!(candidate_names(&zebra, &dir, &names).is_empty());
+        assert_eq!(names.to_vec(), candidate_names(&short, &dir, &names));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn changed_and_new_files_are_always_candidates() {
+        let dir = temp_dir("stale");
+        fs::write(dir.join("same.txt"), "nothing here").unwrap();
+        fs::write(dir.join("grown.txt"), "nothing here").unwrap();
+        fs::write(dir.join("touched.txt"), "nothing here").unwrap();
+        Index::build(&dir).unwrap().save().unwrap();
+
+        fs::write(dir.join("grown.txt"), "nothing here, but now a frog").unwrap();
+        let touched = fs::OpenOptions::new().write(true).open(dir.join("touched.txt")).unwrap();
+        touched.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        fs::write(dir.join("new.txt"), "frog").unwrap();
+
+        let frog = Index::load(&dir).unwrap().candidates("frog", true);
+        let names = ["same.txt", "grown.txt", "touched.txt", "new.txt"];
+        assert_eq!(vec!["grown.txt", "touched.txt", "new.txt"], candidate_names(&frog, &dir, &names));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_or_corrupt_index() {
+        let dir = temp_dir("corrupt");
+        assert!(Index::load(&dir).is_err());
+
+        fs::write(Index::path_in(&dir), "definitely not an index").unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, Index::load(&dir).err().unwrap().kind());
+
+        // Lengths and counts far bigger than the file are rejected before
+        // anything that size is allocated
+        let mut huge_path = MAGIC.to_vec();
+        huge_path.extend_from_slice(&1u32.to_le_bytes());
+        huge_path.extend_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(Index::path_in(&dir), &huge_path).unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, Index::load(&dir).err().unwrap().kind());
+
+        let mut huge_count = MAGIC.to_vec();
+        huge_count.extend_from_slice(&0u32.to_le_bytes());
+        huge_count.extend_from_slice(&1u32.to_le_bytes());
+        huge_count.extend_from_slice(&0u32.to_le_bytes());
+        huge_count.extend_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(Index::path_in(&dir), &huge_count).unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, Index::load(&dir).err().unwrap().kind());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
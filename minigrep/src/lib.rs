@@ -1,48 +1,277 @@
-use std::{env, fs, error::Error};
+use std::{env, io, io::Read, error::Error, fs::File, path::{Path, PathBuf}};
+
+use encoding_rs::{Encoding, UTF_8};
+
+// The searching and the printing live in their own modules so that other
+// programs can use minigrep as a library with their own input and output
+mod decode;
+mod format;
+mod index;
+mod literal;
+mod matcher;
+mod preprocess;
+mod searcher;
+mod sink;
+mod sort;
+mod walk;
+
+use crate::matcher::Matcher;
+
+pub use crate::format::{Template, TemplateSink};
+pub use crate::index::Index;
+pub use crate::preprocess::Preprocessor;
+pub use crate::searcher::Searcher;
+pub use crate::sink::{Sink, StandardSink};
+pub use crate::sort::{Sort, SortBy};
 
 // Extracting logic from main
 // Box<dyn Error> is trait object (covered in Chapter 17)
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    // fs::read_to_string returns Result<String>
-    // ? covered in Chapter 9 returns error value from current function
-    let contents = fs::read_to_string(config.filename)?;
+    // Locking stdout once is faster than println! locking it for every line
+    let stdout = io::stdout();
+    let wtr = stdout.lock();
+
+    match &config.format {
+        Some(template) => {
+            let sink = TemplateSink::new(wtr, template.clone(), &config.filename);
+            if config.only_matching {
+                run_with_sink(&config, sink.only_matching())
+            } else {
+                run_with_sink(&config, sink)
+            }
+        },
+        None if config.only_matching => run_with_sink(&config, StandardSink::new(wtr).only_matching()),
+        None => run_with_sink(&config, StandardSink::new(wtr)),
+    }
+}
+
+// Same as run, but the results go to the given Sink instead of stdout
+pub fn run_with_sink<S: Sink>(config: &Config, mut sink: S) -> Result<(), Box<dyn Error>> {
+    let mut searcher = Searcher::new(&config.query, config.case_sensitive);
+    searcher.encoding = config.encoding;
 
-    let results = if config.case_sensitive {
-        search(&config.query, &contents)
-        
+    let root = Path::new(&config.filename);
+
+    // A single file is searched as before, without paths in the output
+    if !root.is_dir() {
+        if config.use_index {
+            return Err("--index only works when searching a directory".into());
+        }
+        // ? covered in Chapter 9 returns error value from current function
+        search_file(config, &searcher, root, &mut sink)?;
+        return Ok(());
+    }
+
+    // With --index, files the index rules out are never opened
+    let candidates = if config.use_index {
+        Some(Index::load(root)?.candidates(&config.query, config.case_sensitive))
     } else {
-        search_case_insensitive(&config.query, &contents)
+        None
+    };
+    let wanted = |path: &Path| {
+        if index::is_index_file(path) {
+            return false;
+        }
+        match (&candidates, &config.preprocessor) {
+            // The index only knows the raw bytes, not what --pre turns them into
+            (_, Some(pre)) if pre.applies_to(path) => true,
+            // or what they decode to when they aren't UTF-8
+            (Some(_), _) if config.encoding.is_some() || has_other_bom(path) => true,
+            (Some(candidates), _) => candidates.might_match(path),
+            (None, _) => true,
+        }
     };
 
-    for line in results {
-        println!("{}", line);
+    match config.sort {
+        // No sorting: print results as soon as each file is found
+        None => walk::visit_files(root, &mut |path| {
+            if !wanted(path) {
+                return Ok(());
+            }
+            sink.begin(path)?;
+            search_walked_file(config, &searcher, path, &mut sink)
+        })?,
+
+        // Sorting by match count: search everything, then print in order
+        Some(sort) if sort.needs_results() => {
+            let mut results = Vec::new();
+            walk::visit_files(root, &mut |path| {
+                if !wanted(path) {
+                    return Ok(());
+                }
+                let mut buffered = sort::BufferedResults::new(path);
+                search_walked_file(config, &searcher, path, &mut buffered)?;
+                results.push(buffered);
+                Ok(())
+            })?;
+
+            sort.sort_results(&mut results);
+            for buffered in results {
+                buffered.replay(&mut sink)?;
+            }
+        },
+
+        // Sorting by path or time: put the files in order, then search them
+        Some(sort) => {
+            let mut paths: Vec<PathBuf> = Vec::new();
+            walk::visit_files(root, &mut |path| {
+                if wanted(path) {
+                    paths.push(path.to_path_buf());
+                }
+                Ok(())
+            })?;
+
+            for path in sort.sort_paths(paths) {
+                sink.begin(&path)?;
+                search_walked_file(config, &searcher, &path, &mut sink)?;
+            }
+        },
+    };
+
+    Ok(())
+}
+
+// The DIR in `minigrep index build DIR`. Anything else, even starting with
+// "index", is a search: `minigrep index poem.txt` looks for "index".
+pub fn index_dir(args: &[String]) -> Option<&Path> {
+    match args {
+        [_, command, build, dir] if command == "index" && build == "build" => Some(Path::new(dir)),
+        _ => None,
     }
+}
+
+// Builds the index for `minigrep index build DIR`
+pub fn run_index(dir: &Path) -> Result<(), Box<dyn Error>> {
+    let index = Index::build(dir)?;
+    index.save()?;
+    eprintln!("Indexed {} files into {}", index.file_count(), Index::path_in(dir).display());
 
     Ok(())
 }
 
+fn search_file<S: Sink>(config: &Config, searcher: &Searcher, path: &Path, sink: S) -> io::Result<()> {
+    match &config.preprocessor {
+        Some(pre) if pre.applies_to(path) => pre.search_path(searcher, path, sink),
+        _ => searcher.search_path(path, sink),
+    }
+}
+
+// Whether `path` starts with a byte order mark for an encoding other than
+// UTF-8, so what gets searched isn't the bytes in the file
+fn has_other_bom(path: &Path) -> bool {
+    let mut start = [0; 3];
+    let read = File::open(path).and_then(|mut file| file.read(&mut start)).unwrap_or(0);
+    match Encoding::for_bom(&start[..read]) {
+        Some((encoding, _)) => encoding != UTF_8,
+        None => false,
+    }
+}
+
+// Directories often hold binary files that aren't valid UTF-8. Those are
+// skipped instead of stopping the whole search, like grep skips them.
+fn search_walked_file<S: Sink>(config: &Config, searcher: &Searcher, path: &Path, sink: S) -> io::Result<()> {
+    match search_file(config, searcher, path, sink) {
+        Err(ref e) if e.kind() == io::ErrorKind::InvalidData => Ok(()),
+        result => result,
+    }
+}
+
 pub struct Config {
     pub query: String,
     pub filename: String,
     pub case_sensitive: bool,
+    // Set with --pre COMMAND (and optionally one or more --pre-glob GLOB)
+    pub preprocessor: Option<Preprocessor>,
+    // Set with --sort KEY, or --sortr KEY for the reverse order
+    pub sort: Option<Sort>,
+    // Set with --index, which uses the index made by `minigrep index build`
+    pub use_index: bool,
+    // Set with --encoding LABEL, e.g. utf-16le or windows-1252. Without it
+    // files are read as UTF-8, or UTF-16 if they start with a BOM.
+    pub encoding: Option<&'static Encoding>,
+    // Set with -o (or --only-matching) to print only the matched text
+    pub only_matching: bool,
+    // Set with --format TEMPLATE, see Template for the placeholders
+    pub format: Option<Template>,
 }
 
 impl Config{
     pub fn new(args: &[String]) -> Result<Config, &'static str> {
-        if args.len() < 3 {
+        // Options can appear anywhere, everything else is positional.
+        // Index 0 is the program's name, so we skip it.
+        let mut positional = Vec::new();
+        let mut pre_command = None;
+        let mut pre_globs = Vec::new();
+        let mut sort = None;
+        let mut use_index = false;
+        let mut encoding = None;
+        let mut only_matching = false;
+        let mut format = None;
+
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--pre" => match iter.next() {
+                    Some(command) => pre_command = Some(command.clone()),
+                    None => return Err("--pre needs a command"),
+                },
+                "--pre-glob" => match iter.next() {
+                    Some(glob) => pre_globs.push(glob.clone()),
+                    None => return Err("--pre-glob needs a glob"),
+                },
+                "--sort" | "--sortr" => match iter.next() {
+                    Some(key) => sort = Some(Sort { by: key.parse()?, reverse: arg == "--sortr" }),
+                    None => return Err("--sort needs one of path, modified, accessed, created or matches"),
+                },
+                "--index" => use_index = true,
+                "-o" | "--only-matching" => only_matching = true,
+                "--format" => match iter.next() {
+                    Some(template) => format = Some(Template::parse(template)?),
+                    None => return Err("--format needs a template such as '{path}:{line}:{col}: {text}'"),
+                },
+                "--encoding" => match iter.next() {
+                    Some(label) => match Encoding::for_label(label.as_bytes()) {
+                        Some(found) => encoding = Some(found),
+                        None => return Err("unknown encoding, try utf-8, utf-16le, utf-16be or windows-1252"),
+                    },
+                    None => return Err("--encoding needs an encoding name"),
+                },
+                _ => positional.push(arg),
+            }
+        }
+
+        if positional.len() < 2 {
             return Err("not enough arguments");
         }
 
-        // Index 0 is the program's name, so we start with index 1 then
-        // index 2 for the input
-        let query = args[1].clone();
-        let filename = args[2].clone();
+        let query = positional[0].clone();
+        let filename = positional[1].clone();
         // Many Rustaceans avoid using `clone` because of its runtime cost.
         // We'll learn more efficient methods in Chapter 13
 
         let case_sensitive = env::var("CASE_INSENSITIVE").is_err();
 
-        Ok(Config { query, filename, case_sensitive })
+        let preprocessor = match pre_command {
+            Some(command) => {
+                let mut preprocessor = Preprocessor::new(&command);
+                preprocessor.globs = pre_globs;
+                Some(preprocessor)
+            },
+            None if !pre_globs.is_empty() => return Err("--pre-glob can only be used with --pre"),
+            None => None,
+        };
+
+        Ok(Config {
+            query,
+            filename,
+            case_sensitive,
+            preprocessor,
+            sort,
+            use_index,
+            encoding,
+            only_matching,
+            format,
+        })
     }
 }
 
@@ -50,27 +279,25 @@ impl Config{
 // sure the lifetime of the return value matches the lifetime of the
 // contents
 pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-    let mut results = Vec::new();
-
-    // lines is a method that allows iteration over lines of a string
-    for line in contents.lines() {
-        // contains checks if string contains the input
-        if line.contains(query) {
-            results.push(line);
-        }
-    }
-
-    results
+    matching_lines(&Matcher::new(query, true), contents)
 }
 
 pub fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-    let query = query.to_lowercase();
+    matching_lines(&Matcher::new(query, false), contents)
+}
+
+// The first version of search split contents with lines() and called
+// contains on every line. The Matcher searches the whole string at once
+// and only works out where a line starts and ends when it finds a hit.
+fn matching_lines<'a>(matcher: &Matcher, contents: &'a str) -> Vec<&'a str> {
     let mut results = Vec::new();
+    let mut from = 0;
 
-    for line in contents.lines() {
-        if line.to_lowercase().contains(&query) {
-            results.push(line);
-        }
+    while let Some(line) = matcher.find_line(contents.as_bytes(), from) {
+        // Lines always start and end next to an ASCII '\n' or '\r', so
+        // these are valid places to slice a str
+        results.push(&contents[line.start..line.end]);
+        from = line.next;
     }
 
     results
@@ -135,6 +362,235 @@ mod tests {
         }
     }
 
+    #[test]
+    fn run_with_sink_captures_output() {
+        let arr = ["zero".to_string(), "nobody".to_string(), "poem.txt".to_string()];
+        let config = Config::new(&arr).unwrap();
+
+        let mut sink = StandardSink::new(Vec::new());
+        run_with_sink(&config, &mut sink).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("I'm nobody! Who are you?\nAre you nobody, too?\n", output);
+    }
+
+    #[test]
+    fn run_with_sink_no_file() {
+        let arr = ["zero".to_string(), "the".to_string(), "test.txt".to_string()];
+        let config = Config::new(&arr).unwrap();
+
+        let mut sink = StandardSink::new(Vec::new());
+        assert!(run_with_sink(&config, &mut sink).is_err());
+    }
+
+    #[test]
+    fn new_config_options() {
+        let arr: Vec<String> = ["zero", "--pre", "pdftotext.sh", "one", "--pre-glob", "*.pdf", "two"]
+            .iter().map(|s| s.to_string()).collect();
+        let config = Config::new(&arr).unwrap();
+
+        assert_eq!("one", config.query);
+        assert_eq!("two", config.filename);
+        let pre = config.preprocessor.unwrap();
+        assert_eq!("pdftotext.sh", pre.command);
+        assert_eq!(vec!["*.pdf"], pre.globs);
+    }
+
+    #[test]
+    fn new_config_option_errors() {
+        let arr: Vec<String> = ["zero", "one", "two", "--pre"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(Err("--pre needs a command"), Config::new(&arr).map(|_| ()));
+
+        let arr: Vec<String> = ["zero", "--pre-glob", "*.pdf", "one", "two"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(Err("--pre-glob can only be used with --pre"), Config::new(&arr).map(|_| ()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_with_preprocessor() {
+        let arr: Vec<String> = ["zero", "--pre", "tests/upper.sh", "DREARY", "poem.txt"]
+            .iter().map(|s| s.to_string()).collect();
+        let config = Config::new(&arr).unwrap();
+
+        let mut sink = StandardSink::new(Vec::new());
+        run_with_sink(&config, &mut sink).unwrap();
+        assert_eq!(b"HOW DREARY TO BE SOMEBODY!\n".to_vec(), sink.into_inner());
+
+        // The glob doesn't match poem.txt, so the raw file is searched instead
+        let arr: Vec<String> = ["zero", "--pre", "tests/upper.sh", "--pre-glob", "*.pdf", "DREARY", "poem.txt"]
+            .iter().map(|s| s.to_string()).collect();
+        let config = Config::new(&arr).unwrap();
+
+        let mut sink = StandardSink::new(Vec::new());
+        run_with_sink(&config, &mut sink).unwrap();
+        assert!(sink.into_inner().is_empty());
+    }
+
+    #[test]
+    fn new_config_sort() {
+        let arr: Vec<String> = ["zero", "one", "two", "--sortr", "modified"].iter().map(|s| s.to_string()).collect();
+        let config = Config::new(&arr).unwrap();
+        assert_eq!(Some(Sort { by: SortBy::Modified, reverse: true }), config.sort);
+
+        let arr: Vec<String> = ["zero", "--sort", "size", "one", "two"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&arr).is_err());
+    }
+
+    #[test]
+    fn run_directory_sorted() {
+        let dir = env::temp_dir().join(format!("minigrep-run-sorted-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("b.txt"), "rust\nrust\nrust\n").unwrap();
+        std::fs::write(dir.join("a.txt"), "rust\nrust\n").unwrap();
+        std::fs::write(dir.join("sub/c.txt"), "rust\n").unwrap();
+        // Not UTF-8, so it is skipped
+        std::fs::write(dir.join("d.bin"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let run_sorted = |args: &[&str]| {
+            let mut arr: Vec<String> = vec!["zero".to_string(), "rust".to_string(), dir.display().to_string()];
+            arr.extend(args.iter().map(|s| s.to_string()));
+            let config = Config::new(&arr).unwrap();
+
+            let mut sink = StandardSink::new(Vec::new());
+            run_with_sink(&config, &mut sink).unwrap();
+            String::from_utf8(sink.into_inner()).unwrap()
+        };
+        let by_path = run_sorted(&["--sort", "path"]);
+        let by_matches = run_sorted(&["--sortr", "matches"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let a = dir.join("a.txt").display().to_string();
+        let b = dir.join("b.txt").display().to_string();
+        let c = dir.join("sub").join("c.txt").display().to_string();
+        assert_eq!(format!("{a}:rust\n{a}:rust\n{b}:rust\n{b}:rust\n{b}:rust\n{c}:rust\n", a = a, b = b, c = c), by_path);
+        assert_eq!(format!("{b}:rust\n{b}:rust\n{b}:rust\n{a}:rust\n{a}:rust\n{c}:rust\n", a = a, b = b, c = c), by_matches);
+    }
+
+    #[test]
+    fn run_with_index() {
+        let dir = env::temp_dir().join(format!("minigrep-run-index-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("poem.txt"), "How public, like a frog").unwrap();
+        std::fs::write(dir.join("other.txt"), "nothing to see").unwrap();
+
+        let args = |extra: &[&str]| -> Vec<String> {
+            let mut arr = vec!["zero".to_string()];
+            arr.extend(extra.iter().map(|s| s.to_string()));
+            arr
+        };
+        let dir_name = dir.display().to_string();
+
+        // Searching with --index before building one is an error
+        let config = Config::new(&args(&["--index", "frog", &dir_name])).unwrap();
+        assert!(run_with_sink(&config, StandardSink::new(Vec::new())).is_err());
+
+        run_index(&dir).unwrap();
+
+        // Added after the index was built, so it has to be searched directly
+        std::fs::write(dir.join("new.txt"), "a frog too").unwrap();
+
+        let mut sink = StandardSink::new(Vec::new());
+        run_with_sink(&config, &mut sink).unwrap();
+        let mut lines: Vec<String> = String::from_utf8(sink.into_inner()).unwrap().lines().map(String::from).collect();
+        lines.sort();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            vec![
+                format!("{}:a frog too", dir.join("new.txt").display()),
+                format!("{}:How public, like a frog", dir.join("poem.txt").display()),
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn run_with_index_decodes_like_the_search() {
+        let dir = env::temp_dir().join(format!("minigrep-run-index-decoded-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("latin1.txt"), b"un caf\xe9 noir").unwrap();
+        let mut utf16 = vec![0xff, 0xfe];
+        utf16.extend("a frog\n".encode_utf16().flat_map(|unit| unit.to_le_bytes().to_vec()));
+        std::fs::write(dir.join("utf16.txt"), utf16).unwrap();
+
+        let dir_name = dir.display().to_string();
+        run_index(&dir).unwrap();
+        let search = |args: &[&str]| {
+            let mut arr = vec!["zero".to_string()];
+            arr.extend(args.iter().map(|s| s.to_string()));
+            arr.push(dir_name.clone());
+            let mut sink = StandardSink::new(Vec::new());
+            run_with_sink(&Config::new(&arr).unwrap(), &mut sink).unwrap();
+            String::from_utf8(sink.into_inner()).unwrap()
+        };
+        // Neither is in the index's raw bytes as UTF-8
+        let latin1 = search(&["--index", "--encoding", "latin1", "café"]);
+        let utf16 = search(&["--index", "frog"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(format!("{}:un café noir\n", dir.join("latin1.txt").display()), latin1);
+        assert_eq!(format!("{}:a frog\n", dir.join("utf16.txt").display()), utf16);
+    }
+
+    #[test]
+    fn index_command() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|s| s.to_string()).collect() };
+        assert_eq!(Some(Path::new("docs")), index_dir(&args(&["zero", "index", "build", "docs"])));
+        assert_eq!(None, index_dir(&args(&["zero", "index", "poem.txt"])));
+        assert_eq!(None, index_dir(&args(&["zero", "index", "rebuild", "docs"])));
+        assert_eq!(None, index_dir(&args(&["zero", "-i", "index", "build", "docs"])));
+    }
+
+    #[test]
+    fn search_for_the_word_index() {
+        let path = env::temp_dir().join(format!("minigrep-index-query-{}.txt", std::process::id()));
+        std::fs::write(&path, "see the index
+at the back
+").unwrap();
+        let arr = ["zero".to_string(), "index".to_string(), path.display().to_string()];
+        assert_eq!(None, index_dir(&arr));
+
+        let mut sink = StandardSink::new(Vec::new());
+        run_with_sink(&Config::new(&arr).unwrap(), &mut sink).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!("see the index\n", String::from_utf8(sink.into_inner()).unwrap());
+    }
+
+    #[test]
+    fn new_config_encoding() {
+        let arr: Vec<String> = ["zero", "--encoding", "UTF-16LE", "one", "two"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(Some(encoding_rs::UTF_16LE), Config::new(&arr).unwrap().encoding);
+
+        let arr: Vec<String> = ["zero", "--encoding", "klingon", "one", "two"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&arr).is_err());
+    }
+
+    #[test]
+    fn new_config_output_options() {
+        let arr: Vec<String> = ["zero", "-o", "--format", "{line}: {text}", "one", "two"].iter().map(|s| s.to_string()).collect();
+        let config = Config::new(&arr).unwrap();
+        assert!(config.only_matching);
+        assert_eq!(Some(Template::parse("{line}: {text}").unwrap()), config.format);
+
+        let arr: Vec<String> = ["zero", "--format", "{nope}", "one", "two"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&arr).is_err());
+    }
+
+    #[test]
+    fn run_with_template() {
+        let arr: Vec<String> = ["zero", "-o", "--format", "{path}:{line}:{col}: {text}", "body", "poem.txt"]
+            .iter().map(|s| s.to_string()).collect();
+        let config = Config::new(&arr).unwrap();
+
+        let template = config.format.clone().unwrap();
+        let mut sink = TemplateSink::new(Vec::new(), template, &config.filename).only_matching();
+        run_with_sink(&config, &mut sink).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("poem.txt:1:7: body\npoem.txt:2:11: body\npoem.txt:6:22: body\n", output);
+    }
+
     #[test]
     fn one_result() {
         let query = "duct";
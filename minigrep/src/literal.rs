@@ -0,0 +1,146 @@
+// Boyer-Moore-Horspool substring search.
+//
+// The naive way to find a needle is to try it at every position of the
+// haystack. Horspool lines the needle up and compares from its *last*
+// byte. Whatever byte of the haystack sits under the end of the needle
+// tells us how far we can jump: if that byte doesn't appear in the needle
+// at all we can skip the whole needle length at once. For a query of m
+// bytes that means looking at roughly n/m bytes of the input instead of n.
+pub struct Finder {
+    needle: Vec<u8>,
+    // How far to move the needle when the haystack byte under its last
+    // position is the index into this table
+    skip: [usize; 256],
+    // ASCII letters match regardless of case (the needle is stored lowercase)
+    ascii_case_insensitive: bool,
+}
+
+impl Finder {
+    pub fn new(needle: &[u8]) -> Finder {
+        Finder::build(needle.to_vec(), false)
+    }
+
+    // Matches ASCII letters in either case, e.g. "rust" finds "Rust" and "RUST"
+    pub fn new_ascii_case_insensitive(needle: &[u8]) -> Finder {
+        Finder::build(needle.to_ascii_lowercase(), true)
+    }
+
+    fn build(needle: Vec<u8>, ascii_case_insensitive: bool) -> Finder {
+        let len = needle.len();
+        let mut skip = [len.max(1); 256];
+
+        // The last byte is left out: if it were in the table we could
+        // compute a skip of 0 and never move
+        for (i, &byte) in needle.iter().enumerate().take(len.saturating_sub(1)) {
+            let distance = len - 1 - i;
+            skip[byte as usize] = distance;
+            if ascii_case_insensitive {
+                skip[byte.to_ascii_uppercase() as usize] = distance;
+            }
+        }
+
+        Finder { needle, skip, ascii_case_insensitive }
+    }
+
+    pub fn needle_len(&self) -> usize {
+        self.needle.len()
+    }
+
+    // Returns the start of the first match at or after `from`
+    pub fn find(&self, haystack: &[u8], from: usize) -> Option<usize> {
+        let n = self.needle.len();
+        if from > haystack.len() {
+            return None;
+        }
+        if n == 0 {
+            return Some(from);
+        }
+        // A single byte has nothing to skip over, a plain scan is faster
+        if n == 1 && !self.ascii_case_insensitive {
+            let byte = self.needle[0];
+            return haystack[from..].iter().position(|&b| b == byte).map(|i| from + i);
+        }
+
+        let last = n - 1;
+        let mut pos = from;
+        while pos + n <= haystack.len() {
+            let tail = haystack[pos + last];
+            if self.byte_eq(tail, self.needle[last]) && self.bytes_eq(&haystack[pos..pos + last], &self.needle[..last]) {
+                return Some(pos);
+            }
+            pos += self.skip[tail as usize];
+        }
+
+        None
+    }
+
+    fn byte_eq(&self, hay: u8, needle: u8) -> bool {
+        if self.ascii_case_insensitive {
+            hay.to_ascii_lowercase() == needle
+        } else {
+            hay == needle
+        }
+    }
+
+    fn bytes_eq(&self, hay: &[u8], needle: &[u8]) -> bool {
+        if self.ascii_case_insensitive {
+            hay.eq_ignore_ascii_case(needle)
+        } else {
+            hay == needle
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What Finder should agree with: try the needle at every position
+    fn naive_find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+        (from..=haystack.len().saturating_sub(needle.len()))
+            .find(|&i| i + needle.len() <= haystack.len() && &haystack[i..i + needle.len()] == needle)
+    }
+
+    #[test]
+    fn finds_first_match() {
+        let finder = Finder::new(b"productive");
+        assert_eq!(Some(12), finder.find(b"safe, fast, productive.", 0));
+        assert_eq!(None, finder.find(b"safe, fast, product.", 0));
+    }
+
+    #[test]
+    fn finds_from_offset() {
+        let finder = Finder::new(b"ab");
+        assert_eq!(Some(0), finder.find(b"abxab", 0));
+        assert_eq!(Some(3), finder.find(b"abxab", 1));
+        assert_eq!(None, finder.find(b"abxab", 4));
+        assert_eq!(None, finder.find(b"abxab", 10));
+    }
+
+    #[test]
+    fn empty_and_single_byte_needles() {
+        assert_eq!(Some(2), Finder::new(b"").find(b"abc", 2));
+        assert_eq!(Some(1), Finder::new(b"b").find(b"abc", 0));
+        assert_eq!(Some(1), Finder::new_ascii_case_insensitive(b"B").find(b"abc", 0));
+    }
+
+    #[test]
+    fn ascii_case_insensitive() {
+        let finder = Finder::new_ascii_case_insensitive(b"rUsT");
+        assert_eq!(Some(0), finder.find(b"Rust:", 0));
+        assert_eq!(Some(1), finder.find(b"Trust me.", 0));
+        assert_eq!(None, Finder::new(b"rUsT").find(b"Trust me.", 0));
+    }
+
+    #[test]
+    fn agrees_with_naive_search() {
+        let haystack = b"abaabababbaabababaabbbabababaaababbabbababbaaab";
+        let needles: [&[u8]; 7] = [b"a", b"ab", b"aab", b"babab", b"bbb", b"abababaa", b"ccc"];
+        for needle in needles.iter() {
+            let finder = Finder::new(needle);
+            for from in 0..haystack.len() {
+                assert_eq!(naive_find(haystack, needle, from), finder.find(haystack, from), "needle {:?} from {}", needle, from);
+            }
+        }
+    }
+}
@@ -52,6 +52,15 @@ fn main() {
     // The collect method turns iterator into a collection (like vector)
     let args: Vec<String> = env::args().collect();
 
+    // `minigrep index build DIR` builds an index instead of searching
+    if let Some(dir) = minigrep::index_dir(&args) {
+        if let Err(e) = minigrep::run_index(dir) {
+            eprintln!("Application error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     // Calls Config::new() then either retrieves what's in Ok() or
     // if Err() prints the problem and ends the program
     let config = Config::new(&args).unwrap_or_else(|err| {
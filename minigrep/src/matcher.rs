@@ -0,0 +1,227 @@
+use std::ops::Range;
+
+use crate::literal::Finder;
+
+// Where a matching line sits in a buffer. `end` leaves out the line
+// ending ("\n" or "\r\n") and `next` is where the following line starts.
+#[derive(Debug, PartialEq)]
+pub struct LineMatch {
+    pub start: usize,
+    pub end: usize,
+    pub next: usize,
+}
+
+// Finds matching lines in a whole buffer at once. Instead of splitting the
+// buffer into lines and checking each one, it jumps from hit to hit and
+// only looks for the surrounding line boundaries once it has found one.
+pub enum Matcher {
+    // Boxed because the skip table makes a Finder a couple of kilobytes
+    Literal(Box<Finder>),
+    // Case-insensitive search with a non-ASCII query. Unicode lowercasing
+    // can change the length of a string, so we can't search the raw bytes
+    // and fall back to lowercasing every line like the original code did.
+    Lowercase(String),
+    // A query containing a newline can never match inside a single line
+    Never,
+}
+
+impl Matcher {
+    pub fn new(query: &str, case_sensitive: bool) -> Matcher {
+        if query.contains('\n') {
+            Matcher::Never
+        } else if case_sensitive {
+            Matcher::Literal(Box::new(Finder::new(query.as_bytes())))
+        } else if query.is_ascii() {
+            Matcher::Literal(Box::new(Finder::new_ascii_case_insensitive(query.as_bytes())))
+        } else {
+            Matcher::Lowercase(query.chars().flat_map(fold).collect())
+        }
+    }
+
+    // Returns the first matching line that starts at or after `from`,
+    // which must be the start of a line
+    pub fn find_line(&self, haystack: &[u8], from: usize) -> Option<LineMatch> {
+        match self {
+            Matcher::Literal(finder) => find_literal_line(finder, haystack, from),
+            Matcher::Lowercase(query) => find_lowercase_line(query, haystack, from),
+            Matcher::Never => None,
+        }
+    }
+
+    // Byte ranges of every match in a line that find_line returned, left
+    // to right and not overlapping. An empty query matches the whole line
+    // but has nothing to point at, so it gives no ranges.
+    pub fn find_in_line(&self, line: &str) -> Vec<Range<usize>> {
+        let mut found = Vec::new();
+        match self {
+            Matcher::Literal(finder) if finder.needle_len() > 0 => {
+                let mut from = 0;
+                while let Some(start) = finder.find(line.as_bytes(), from) {
+                    let end = start + finder.needle_len();
+                    found.push(start..end);
+                    from = end;
+                }
+            },
+            Matcher::Lowercase(query) => {
+                let mut from = 0;
+                while let Some(range) = find_lowercase(query, line, from) {
+                    from = range.end;
+                    found.push(range);
+                }
+            },
+            _ => {},
+        }
+        found
+    }
+}
+
+// Lowercases a character for comparing without case. A final sigma ("ς")
+// is only how "σ" is written at the end of a word, so it becomes "σ" too.
+// The query and the lines are lowercased this same way, a character at a
+// time, so a line is only found when there's a match to point at in it.
+fn fold(c: char) -> impl Iterator<Item = char> {
+    c.to_lowercase().map(|c| if c == 'ς' { 'σ' } else { c })
+}
+
+// The first match of `query` (already folded) in `line` at or after `from`
+fn find_lowercase(query: &str, line: &str, mut from: usize) -> Option<Range<usize>> {
+    while from < line.len() {
+        match lowercase_match_at(query, &line[from..]) {
+            Some(length) => return Some(from..from + length),
+            None => from += line[from..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    None
+}
+
+// If `text` starts with something that folds to `query`, returns how many
+// bytes of `text` that was. Lowercasing can change the length of a
+// character, which is why we can't just compare byte lengths.
+fn lowercase_match_at(query: &str, text: &str) -> Option<usize> {
+    let mut lowered = String::new();
+    for (i, c) in text.char_indices() {
+        lowered.extend(fold(c));
+        if !query.starts_with(lowered.as_str()) {
+            return None;
+        }
+        if lowered.len() == query.len() {
+            return Some(i + c.len_utf8());
+        }
+    }
+    None
+}
+
+fn find_literal_line(finder: &Finder, haystack: &[u8], mut from: usize) -> Option<LineMatch> {
+    // There's no line left once we're past the last newline. This is the
+    // same as str::lines, which doesn't count an empty last line.
+    while from < haystack.len() {
+        let hit = finder.find(haystack, from)?;
+        let line = line_around(haystack, hit);
+
+        // The hit could run into the line ending, e.g. a query ending in
+        // "\r". That isn't a match, but a later hit on the same line might be.
+        if hit + finder.needle_len() <= line.end {
+            return Some(line);
+        }
+        from = hit + 1;
+    }
+
+    None
+}
+
+fn find_lowercase_line(query: &str, haystack: &[u8], mut from: usize) -> Option<LineMatch> {
+    while from < haystack.len() {
+        let line = line_around(haystack, from);
+        let text = String::from_utf8_lossy(&haystack[line.start..line.end]);
+        if find_lowercase(query, &text, 0).is_some() {
+            return Some(line);
+        }
+        from = line.next;
+    }
+
+    None
+}
+
+// Finds the boundaries of the line that contains `pos`
+pub fn line_around(haystack: &[u8], pos: usize) -> LineMatch {
+    let start = haystack[..pos].iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    match haystack[pos..].iter().position(|&b| b == b'\n') {
+        Some(i) => {
+            let newline = pos + i;
+            let end = if newline > start && haystack[newline - 1] == b'\r' { newline - 1 } else { newline };
+            LineMatch { start, end, next: newline + 1 }
+        },
+        None => LineMatch { start, end: haystack.len(), next: haystack.len() },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines<'a>(matcher: &Matcher, contents: &'a str) -> Vec<&'a str> {
+        let mut found = Vec::new();
+        let mut from = 0;
+        while let Some(line) = matcher.find_line(contents.as_bytes(), from) {
+            found.push(&contents[line.start..line.end]);
+            from = line.next;
+        }
+        found
+    }
+
+    #[test]
+    fn line_boundaries() {
+        let text = b"one\r\ntwo\nthree";
+        assert_eq!(LineMatch { start: 0, end: 3, next: 5 }, line_around(text, 1));
+        assert_eq!(LineMatch { start: 5, end: 8, next: 9 }, line_around(text, 5));
+        assert_eq!(LineMatch { start: 9, end: 14, next: 14 }, line_around(text, 12));
+    }
+
+    #[test]
+    fn one_line_per_match() {
+        let matcher = Matcher::new("a", true);
+        assert_eq!(vec!["banana", "a"], lines(&matcher, "banana\nkiwi\na\n"));
+    }
+
+    #[test]
+    fn hits_in_line_endings_are_ignored() {
+        let matcher = Matcher::new("b\r", true);
+        assert_eq!(Vec::<&str>::new(), lines(&matcher, "ab\r\nb\r\n"));
+
+        let matcher = Matcher::new("a\nb", true);
+        assert_eq!(Vec::<&str>::new(), lines(&matcher, "a\nb"));
+    }
+
+    #[test]
+    fn empty_query_matches_every_line() {
+        let matcher = Matcher::new("", true);
+        assert_eq!(vec!["a", "", "b"], lines(&matcher, "a\n\nb\n"));
+        assert_eq!(Vec::<&str>::new(), lines(&matcher, ""));
+    }
+
+    #[test]
+    fn match_offsets() {
+        assert_eq!(vec![1..3, 3..5], Matcher::new("an", true).find_in_line("banana"));
+        assert_eq!(vec![0..4, 6..10], Matcher::new("rust", false).find_in_line("Rust, RUST"));
+        assert_eq!(vec![3..8], Matcher::new("über", false).find_in_line("ja Über"));
+        assert!(Matcher::new("", true).find_in_line("banana").is_empty());
+    }
+
+    #[test]
+    fn final_sigma() {
+        // "ΟΔΟΣ" lowercases to "οδος" as a word but to "οδοσ" a letter at a
+        // time; either way every line found has a match to point at
+        for query in ["ΟΔΟΣ", "οδος", "οδοσ"].iter() {
+            let matcher = Matcher::new(query, false);
+            assert_eq!(vec!["Η ΟΔΟΣ", "η οδος"], lines(&matcher, "Η ΟΔΟΣ\nη οδος\nοδηγός"));
+            assert_eq!(vec![3..11], matcher.find_in_line("Η ΟΔΟΣ"));
+            assert_eq!(vec![3..11], matcher.find_in_line("η οδος"));
+        }
+    }
+
+    #[test]
+    fn unicode_case_insensitive() {
+        let matcher = Matcher::new("ÜBER", false);
+        assert_eq!(vec!["über alles", "Über"], lines(&matcher, "über alles\nuber\nÜber"));
+    }
+}
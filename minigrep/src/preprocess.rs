@@ -0,0 +1,172 @@
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::searcher::Searcher;
+use crate::sink::Sink;
+
+// An external command that turns a file into searchable text, for example
+// a PDF-to-text converter. Like ripgrep's --pre, the command is run with
+// the file's path as its only argument and whatever it prints to stdout
+// is searched instead of the file itself.
+pub struct Preprocessor {
+    pub command: String,
+    // Only files whose name matches one of these globs are preprocessed.
+    // No globs means every file goes through the command.
+    pub globs: Vec<String>,
+}
+
+impl Preprocessor {
+    pub fn new(command: &str) -> Preprocessor {
+        Preprocessor {
+            command: command.to_string(),
+            globs: Vec::new(),
+        }
+    }
+
+    pub fn applies_to(&self, path: &Path) -> bool {
+        if self.globs.is_empty() {
+            return true;
+        }
+
+        // A glob without a '/' is matched against just the file name, so
+        // "*.pdf" works no matter which directory the file is in
+        let full = path.to_string_lossy();
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => full.clone(),
+        };
+        self.globs.iter().any(|glob| {
+            if glob.contains('/') {
+                glob_match(glob, &full)
+            } else {
+                glob_match(glob, &name)
+            }
+        })
+    }
+
+    // Runs the command on `path` and searches its output as it streams in,
+    // so large converted files never have to fit in memory
+    pub fn search_path<S: Sink>(&self, searcher: &Searcher, path: &Path, sink: S) -> io::Result<()> {
+        let mut child = Command::new(&self.command)
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                io::Error::new(e.kind(), format!("could not run preprocessor '{}': {}", self.command, e))
+            })?;
+
+        // stdout is always Some because we asked for Stdio::piped() above
+        let stdout = child.stdout.take().expect("preprocessor stdout was not captured");
+        let result = searcher.search_reader(BufReader::new(stdout), sink);
+
+        // Always wait for the child, even if searching failed, so we don't
+        // leave a zombie process behind
+        let status = child.wait()?;
+        result?;
+
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "preprocessor '{}' failed on {}: {}",
+                self.command,
+                path.display(),
+                status
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+// Shell-style wildcard matching: '*' matches any run of characters and
+// '?' matches exactly one. Everything else has to match literally.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Where the last '*' was and how much text it has swallowed so far,
+    // so we can backtrack and let it swallow one more character
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    // Any '*' left over at the end can match nothing
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::StandardSink;
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(glob_match("*.pdf", "report.pdf"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("poem.???", "poem.txt"));
+        assert!(glob_match("*a*b*", "xaxxbx"));
+        assert!(!glob_match("*.pdf", "report.pdf.txt"));
+        assert!(!glob_match("poem.?", "poem.txt"));
+    }
+
+    #[test]
+    fn applies_to_file_name_or_path() {
+        let mut pre = Preprocessor::new("cat");
+        assert!(pre.applies_to(Path::new("poem.txt")));
+
+        pre.globs.push(String::from("*.pdf"));
+        assert!(pre.applies_to(Path::new("docs/report.pdf")));
+        assert!(!pre.applies_to(Path::new("poem.txt")));
+
+        pre.globs.push(String::from("docs/*.txt"));
+        assert!(pre.applies_to(Path::new("docs/notes.txt")));
+        assert!(!pre.applies_to(Path::new("notes.txt")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn searches_command_output() {
+        let pre = Preprocessor::new("tests/upper.sh");
+        let searcher = Searcher::new("NOBODY", true);
+        let mut sink = StandardSink::new(Vec::new());
+        pre.search_path(&searcher, Path::new("poem.txt"), &mut sink).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("I'M NOBODY! WHO ARE YOU?\nARE YOU NOBODY, TOO?\n", output);
+    }
+
+    #[test]
+    fn missing_command_is_an_error() {
+        let pre = Preprocessor::new("tests/does-not-exist.sh");
+        let searcher = Searcher::new("nobody", true);
+        let mut sink = StandardSink::new(Vec::new());
+
+        assert!(pre.search_path(&searcher, Path::new("poem.txt"), &mut sink).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_command_is_an_error() {
+        let pre = Preprocessor::new("false");
+        let searcher = Searcher::new("nobody", true);
+        let mut sink = StandardSink::new(Vec::new());
+
+        assert!(pre.search_path(&searcher, Path::new("poem.txt"), &mut sink).is_err());
+    }
+}
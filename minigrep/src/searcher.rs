@@ -0,0 +1,433 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::Range;
+use std::path::Path;
+use std::str;
+
+use encoding_rs::{Encoding, UTF_8};
+
+use crate::decode::DecodeReader;
+use crate::matcher::Matcher;
+use crate::sink::Sink;
+
+// How much we try to read at a time. Bigger chunks mean fewer calls into
+// the matcher, but a line longer than this still works: the buffer grows.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// The Searcher knows *what* to look for and works on anything that
+// implements BufRead: files, stdin, a network stream or just a byte slice
+// in a test. What happens with the lines it finds is up to the Sink.
+//
+// Input is read in large chunks and each chunk is searched as a whole
+// with a skip-table search (see literal.rs). Line boundaries are only
+// looked for around a hit, and for the context lines we have to report.
+//
+// Input is expected to be UTF-8. A UTF-16 file with a byte order mark is
+// recognised and converted, and `encoding` (--encoding) converts anything
+// else encoding_rs knows, such as Windows-1252, before it is searched.
+pub struct Searcher {
+    pub query: String,
+    pub case_sensitive: bool,
+    // Number of lines to report before and after each match, like grep's
+    // -B and -A. The command line leaves them at 0.
+    pub before_context: usize,
+    pub after_context: usize,
+    pub encoding: Option<&'static Encoding>,
+}
+
+impl Searcher {
+    pub fn new(query: &str, case_sensitive: bool) -> Searcher {
+        Searcher {
+            query: query.to_string(),
+            case_sensitive,
+            before_context: 0,
+            after_context: 0,
+            encoding: None,
+        }
+    }
+
+    // Opens the file and searches it
+    pub fn search_path<P: AsRef<Path>, S: Sink>(&self, path: P, sink: S) -> io::Result<()> {
+        let file = File::open(path)?;
+        self.search_reader(BufReader::new(file), sink)
+    }
+
+    pub fn search_reader<R: BufRead, S: Sink>(&self, mut reader: R, sink: S) -> io::Result<()> {
+        // Look at the first few bytes for a byte order mark without using
+        // them up. fill_buf gives us whatever is already buffered.
+        let bom = Encoding::for_bom(reader.fill_buf()?);
+
+        match (self.encoding, bom) {
+            // Plain UTF-8 is searched as it is, minus its BOM if it has one
+            (None, None) => self.search_utf8(reader, sink),
+            (None, Some((encoding, bom_length))) if encoding == UTF_8 => {
+                reader.consume(bom_length);
+                self.search_utf8(reader, sink)
+            },
+            // Anything else is converted to UTF-8 on the way in
+            (Some(encoding), _) | (None, Some((encoding, _))) => {
+                self.search_utf8(DecodeReader::new(reader, encoding), sink)
+            },
+        }
+    }
+
+    fn search_utf8<R: Read, S: Sink>(&self, mut reader: R, mut sink: S) -> io::Result<()> {
+        let matcher = Matcher::new(&self.query, self.case_sensitive);
+        let mut lines = LineState::new(self);
+        let mut buffer: Vec<u8> = Vec::with_capacity(CHUNK_SIZE);
+
+        loop {
+            let filled = buffer.len();
+            buffer.resize(filled + CHUNK_SIZE, 0);
+            let read = read_some(&mut reader, &mut buffer[filled..])?;
+            buffer.truncate(filled + read);
+            let eof = read == 0;
+
+            // Only whole lines are searched. Whatever comes after the last
+            // newline waits for the next chunk, unless there is no more input.
+            let complete = if eof {
+                buffer.len()
+            } else {
+                match buffer[filled..].iter().rposition(|&b| b == b'\n') {
+                    Some(i) => filled + i + 1,
+                    None => continue,
+                }
+            };
+
+            self.search_chunk(&matcher, &buffer[..complete], &mut lines, &mut sink)?;
+            buffer.drain(..complete);
+
+            if eof {
+                break;
+            }
+        }
+
+        sink.finish()
+    }
+
+    fn search_chunk<S: Sink>(&self, matcher: &Matcher, chunk: &[u8], lines: &mut LineState, sink: &mut S) -> io::Result<()> {
+        let mut cursor = 0;
+        while let Some(found) = matcher.find_line(chunk, cursor) {
+            lines.skip(&chunk[cursor..found.start], sink)?;
+            let line = to_str(&chunk[found.start..found.end])?;
+            lines.matched(line, &matcher.find_in_line(line), sink)?;
+            cursor = found.next;
+        }
+        lines.skip(&chunk[cursor..], sink)
+    }
+}
+
+// Keeps track of line numbers and context between matches, and between
+// chunks, since the lines before a match may have come in an earlier chunk
+struct LineState {
+    before_context: usize,
+    after_context: usize,
+    // Number of the next line we'll see
+    line_number: usize,
+    after_remaining: usize,
+    last_reported: Option<usize>,
+    // The last few lines that didn't match, in case the next line does
+    before: VecDeque<(usize, String)>,
+}
+
+impl LineState {
+    fn new(searcher: &Searcher) -> LineState {
+        LineState {
+            before_context: searcher.before_context,
+            after_context: searcher.after_context,
+            line_number: 1,
+            after_remaining: 0,
+            last_reported: None,
+            before: VecDeque::with_capacity(searcher.before_context),
+        }
+    }
+
+    fn matched<S: Sink>(&mut self, line: &str, matches: &[Range<usize>], sink: &mut S) -> io::Result<()> {
+        // A match flushes whatever context was waiting in front of it
+        while let Some((number, context)) = self.before.pop_front() {
+            self.check_break(number, sink)?;
+            sink.context(number, &context)?;
+            self.last_reported = Some(number);
+        }
+
+        self.check_break(self.line_number, sink)?;
+        sink.matched(self.line_number, line, matches)?;
+        self.last_reported = Some(self.line_number);
+        self.line_number += 1;
+        self.after_remaining = self.after_context;
+        Ok(())
+    }
+
+    // Goes past lines that didn't match. Without context all we need is
+    // how many there were; with context we only look at the first few
+    // (after the previous match) and the last few (before the next one).
+    fn skip<S: Sink>(&mut self, mut skipped: &[u8], sink: &mut S) -> io::Result<()> {
+        while self.after_remaining > 0 && !skipped.is_empty() {
+            let (line, rest) = split_line(skipped);
+            sink.context(self.line_number, to_str(line)?)?;
+            self.last_reported = Some(self.line_number);
+            self.line_number += 1;
+            self.after_remaining -= 1;
+            skipped = rest;
+        }
+
+        let count = count_lines(skipped);
+        if self.before_context == 0 || count == 0 {
+            self.line_number += count;
+            return Ok(());
+        }
+
+        // Jump over the lines that are too far from the next match to matter
+        let keep = count.min(self.before_context);
+        if keep == self.before_context {
+            self.before.clear();
+        }
+        for _ in 0..count - keep {
+            skipped = split_line(skipped).1;
+        }
+        self.line_number += count - keep;
+
+        while !skipped.is_empty() {
+            let (line, rest) = split_line(skipped);
+            if self.before.len() == self.before_context {
+                self.before.pop_front();
+            }
+            self.before.push_back((self.line_number, to_str(line)?.to_string()));
+            self.line_number += 1;
+            skipped = rest;
+        }
+
+        Ok(())
+    }
+
+    // Tells the sink when the next line doesn't follow the last one it got.
+    // Only matters when context is turned on, otherwise every line is a match.
+    fn check_break<S: Sink>(&self, next: usize, sink: &mut S) -> io::Result<()> {
+        if self.before_context == 0 && self.after_context == 0 {
+            return Ok(());
+        }
+        match self.last_reported {
+            Some(last) if next > last + 1 => sink.context_break(),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Reads as much as is available, retrying if a signal interrupted us
+fn read_some<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match reader.read(buf) {
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+// Splits off the first line, without its line ending, from the rest
+fn split_line(bytes: &[u8]) -> (&[u8], &[u8]) {
+    match bytes.iter().position(|&b| b == b'\n') {
+        Some(i) => {
+            let line = &bytes[..i];
+            (line.strip_suffix(b"\r").unwrap_or(line), &bytes[i + 1..])
+        },
+        None => (bytes, &[]),
+    }
+}
+
+// A final line without a newline still counts as a line
+fn count_lines(bytes: &[u8]) -> usize {
+    let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+    match bytes.last() {
+        Some(b'\n') | None => newlines,
+        Some(_) => newlines + 1,
+    }
+}
+
+// Only the lines we hand to the Sink are checked for valid UTF-8. Files
+// that aren't text are reported as InvalidData once something matches.
+fn to_str(bytes: &[u8]) -> io::Result<&str> {
+    str::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::StandardSink;
+
+    // Keeps track of every callback so tests can check what the Searcher did
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Vec<String>,
+        offsets: Vec<Vec<Range<usize>>>,
+        finished: bool,
+    }
+
+    impl Sink for RecordingSink {
+        fn matched(&mut self, line_number: usize, line: &str, matches: &[Range<usize>]) -> io::Result<()> {
+            self.events.push(format!("{}:{}", line_number, line));
+            self.offsets.push(matches.to_vec());
+            Ok(())
+        }
+
+        fn context(&mut self, line_number: usize, line: &str) -> io::Result<()> {
+            self.events.push(format!("{}-{}", line_number, line));
+            Ok(())
+        }
+
+        fn context_break(&mut self) -> io::Result<()> {
+            self.events.push(String::from("--"));
+            Ok(())
+        }
+
+        fn finish(&mut self) -> io::Result<()> {
+            self.finished = true;
+            Ok(())
+        }
+    }
+
+    const CONTENTS: &str = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Duct tape.
+Trust me.
+one
+two
+three
+rust belt";
+
+    #[test]
+    fn matches_with_line_numbers() {
+        let searcher = Searcher::new("duct", true);
+        let mut sink = RecordingSink::default();
+        searcher.search_reader(CONTENTS.as_bytes(), &mut sink).unwrap();
+
+        assert_eq!(vec!["2:safe, fast, productive."], sink.events);
+        assert!(sink.finished);
+    }
+
+    #[test]
+    fn case_insensitive_matches() {
+        let searcher = Searcher::new("rUsT", false);
+        let mut sink = RecordingSink::default();
+        searcher.search_reader(CONTENTS.as_bytes(), &mut sink).unwrap();
+
+        assert_eq!(vec!["1:Rust:", "5:Trust me.", "9:rust belt"], sink.events);
+        assert_eq!(vec![vec![0..4], vec![1..5], vec![0..4]], sink.offsets);
+    }
+
+    #[test]
+    fn context_lines_and_breaks() {
+        let mut searcher = Searcher::new("rust", false);
+        searcher.before_context = 1;
+        searcher.after_context = 1;
+        let mut sink = RecordingSink::default();
+        searcher.search_reader(CONTENTS.as_bytes(), &mut sink).unwrap();
+
+        assert_eq!(
+            vec!["1:Rust:", "2-safe, fast, productive.", "--", "4-Duct tape.", "5:Trust me.", "6-one", "--", "8-three", "9:rust belt"],
+            sink.events
+        );
+    }
+
+    #[test]
+    fn overlapping_context_is_not_repeated() {
+        let mut searcher = Searcher::new("t", true);
+        searcher.before_context = 2;
+        searcher.after_context = 2;
+        let mut sink = RecordingSink::default();
+        searcher.search_reader("a\nt1\nb\nt2\nc".as_bytes(), &mut sink).unwrap();
+
+        assert_eq!(vec!["1-a", "2:t1", "3-b", "4:t2", "5-c"], sink.events);
+    }
+
+    #[test]
+    fn crlf_is_removed() {
+        let searcher = Searcher::new("b", true);
+        let mut sink = RecordingSink::default();
+        searcher.search_reader("a\r\nb\r\n".as_bytes(), &mut sink).unwrap();
+
+        assert_eq!(vec!["2:b"], sink.events);
+    }
+
+    #[test]
+    fn context_across_chunks() {
+        // Far more than one chunk, with a match right at the end
+        let mut contents = String::new();
+        for i in 0..20_000 {
+            contents.push_str(&format!("line {}\n", i));
+        }
+        contents.push_str("needle\nlast");
+
+        let mut searcher = Searcher::new("needle", true);
+        searcher.before_context = 2;
+        searcher.after_context = 2;
+        let mut sink = RecordingSink::default();
+        searcher.search_reader(contents.as_bytes(), &mut sink).unwrap();
+
+        assert_eq!(vec!["19999-line 19998", "20000-line 19999", "20001:needle", "20002-last"], sink.events);
+    }
+
+    #[test]
+    fn invalid_utf8_is_only_an_error_when_reported() {
+        let searcher = Searcher::new("b", true);
+        let mut sink = RecordingSink::default();
+        searcher.search_reader(&b"\xff\nb\n"[..], &mut sink).unwrap();
+        assert_eq!(vec!["2:b"], sink.events);
+
+        let mut sink = RecordingSink::default();
+        let error = searcher.search_reader(&b"\xffb\n"[..], &mut sink).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    fn utf16le_with_bom(text: &str) -> Vec<u8> {
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes().to_vec()));
+        bytes
+    }
+
+    #[test]
+    fn utf16_with_bom_is_transcoded() {
+        let searcher = Searcher::new("über", false);
+        let mut sink = RecordingSink::default();
+        searcher.search_reader(&utf16le_with_bom("Über\r\nnein\r\nüber alles\r\n")[..], &mut sink).unwrap();
+
+        // No "\r" left on the end of the lines
+        assert_eq!(vec!["1:Über", "3:über alles"], sink.events);
+    }
+
+    #[test]
+    fn utf8_bom_is_removed() {
+        let searcher = Searcher::new("Rust", true);
+        let mut sink = RecordingSink::default();
+        searcher.search_reader(&b"\xef\xbb\xbfRust:\r\n"[..], &mut sink).unwrap();
+
+        assert_eq!(vec!["1:Rust:"], sink.events);
+    }
+
+    #[test]
+    fn explicit_encoding() {
+        let mut searcher = Searcher::new("caf", true);
+        let mut sink = RecordingSink::default();
+        let latin1 = &b"un caf\xe9\r\nun th\xe9\r\n"[..];
+
+        // Without --encoding the Latin-1 é isn't valid UTF-8
+        assert!(searcher.search_reader(latin1, &mut sink).is_err());
+
+        searcher.encoding = Encoding::for_label(b"latin1");
+        let mut sink = RecordingSink::default();
+        searcher.search_reader(latin1, &mut sink).unwrap();
+        assert_eq!(vec!["1:un café"], sink.events);
+    }
+
+    #[test]
+    fn output_captured_in_memory() {
+        let searcher = Searcher::new("the", true);
+        let mut sink = StandardSink::new(Vec::new());
+        searcher.search_path("poem.txt", &mut sink).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("Then there's a pair of us - don't tell!\nTo tell your name the livelong day\n", output);
+    }
+}
@@ -0,0 +1,184 @@
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+
+// A Sink receives everything the Searcher finds. Splitting the search
+// from the output means other programs can embed minigrep and decide
+// for themselves what to do with the results.
+//
+// Every callback returns io::Result so that a Sink writing somewhere
+// (stdout, a file, a socket) can hand write errors back to the Searcher,
+// which then stops searching.
+pub trait Sink {
+    // Called before each file when more than one file is being searched,
+    // e.g. when searching a directory. Not called for a single file.
+    fn begin(&mut self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Called for every line that contains the query. `matches` holds the
+    // byte range of each match within `line`, from left to right.
+    fn matched(&mut self, line_number: usize, line: &str, matches: &[Range<usize>]) -> io::Result<()>;
+
+    // Called for the lines just before and after a match when the Searcher
+    // was given before_context or after_context. The minigrep command never
+    // asks for context, and most sinks don't care about it, so the default
+    // ignores it.
+    fn context(&mut self, _line_number: usize, _line: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Called between two groups of context lines that aren't next to
+    // each other, which is where grep prints "--"
+    fn context_break(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Called once the end of the input has been reached
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// A mutable reference to a Sink is also a Sink, so callers can keep
+// ownership of their sink and look at it after the search is done
+impl<S: Sink + ?Sized> Sink for &mut S {
+    fn begin(&mut self, path: &Path) -> io::Result<()> {
+        (**self).begin(path)
+    }
+
+    fn matched(&mut self, line_number: usize, line: &str, matches: &[Range<usize>]) -> io::Result<()> {
+        (**self).matched(line_number, line, matches)
+    }
+
+    fn context(&mut self, line_number: usize, line: &str) -> io::Result<()> {
+        (**self).context(line_number, line)
+    }
+
+    fn context_break(&mut self) -> io::Result<()> {
+        (**self).context_break()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+// The original minigrep behaviour: print each line as it is found.
+// It is generic over Write so tests can pass a Vec<u8> instead of stdout.
+// When searching several files each line starts with the file's path,
+// followed by ':' for matches and '-' for context, just like grep.
+pub struct StandardSink<W: Write> {
+    wtr: W,
+    path: Option<String>,
+    only_matching: bool,
+}
+
+impl<W: Write> StandardSink<W> {
+    pub fn new(wtr: W) -> StandardSink<W> {
+        StandardSink { wtr, path: None, only_matching: false }
+    }
+
+    // Like grep -o: print each match on its own line instead of the whole
+    // line it was found in. Context lines aren't printed in this mode.
+    pub fn only_matching(mut self) -> StandardSink<W> {
+        self.only_matching = true;
+        self
+    }
+
+    // Gives back the writer, e.g. to read what was written to a Vec<u8>
+    pub fn into_inner(self) -> W {
+        self.wtr
+    }
+}
+
+impl<W: Write> Sink for StandardSink<W> {
+    fn begin(&mut self, path: &Path) -> io::Result<()> {
+        self.path = Some(path.display().to_string());
+        Ok(())
+    }
+
+    fn matched(&mut self, _line_number: usize, line: &str, matches: &[Range<usize>]) -> io::Result<()> {
+        if !self.only_matching {
+            return self.write_line(':', line);
+        }
+        for range in matches {
+            self.write_line(':', &line[range.clone()])?;
+        }
+        Ok(())
+    }
+
+    fn context(&mut self, _line_number: usize, line: &str) -> io::Result<()> {
+        if self.only_matching {
+            return Ok(());
+        }
+        self.write_line('-', line)
+    }
+
+    fn context_break(&mut self) -> io::Result<()> {
+        if self.only_matching {
+            return Ok(());
+        }
+        writeln!(self.wtr, "--")
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.wtr.flush()
+    }
+}
+
+impl<W: Write> StandardSink<W> {
+    // `separator` goes between the path and the text when there is a path
+    fn write_line(&mut self, separator: char, text: &str) -> io::Result<()> {
+        match &self.path {
+            Some(path) => writeln!(self.wtr, "{}{}{}", path, separator, text),
+            None => writeln!(self.wtr, "{}", text),
+        }
+    }
+}
+
+#[cfg(test)]
+// Single-match lines are written as &[start..end], which is what we mean
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_sink_writes_lines() {
+        let mut sink = StandardSink::new(Vec::new());
+        sink.context(1, "before").unwrap();
+        sink.matched(2, "match", &[0..5]).unwrap();
+        sink.context_break().unwrap();
+        sink.matched(9, "another", &[0..7]).unwrap();
+        sink.finish().unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("before\nmatch\n--\nanother\n", output);
+    }
+
+    #[test]
+    fn standard_sink_prefixes_paths() {
+        let mut sink = StandardSink::new(Vec::new());
+        sink.begin(Path::new("a.txt")).unwrap();
+        sink.matched(1, "match", &[0..5]).unwrap();
+        sink.context(2, "after").unwrap();
+        sink.begin(Path::new("b.txt")).unwrap();
+        sink.matched(3, "another", &[0..7]).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("a.txt:match\na.txt-after\nb.txt:another\n", output);
+    }
+
+    #[test]
+    fn standard_sink_only_matching() {
+        let mut sink = StandardSink::new(Vec::new()).only_matching();
+        sink.context(1, "before").unwrap();
+        sink.matched(2, "see https://a.example and https://b.example", &[4..21, 26..43]).unwrap();
+        sink.context_break().unwrap();
+        sink.begin(Path::new("log.txt")).unwrap();
+        sink.matched(7, "id=42", &[3..5]).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("https://a.example\nhttps://b.example\nlog.txt:42\n", output);
+    }
+}
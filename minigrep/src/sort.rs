@@ -0,0 +1,209 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use crate::sink::Sink;
+
+// What --sort (or --sortr for the reverse order) orders files by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortBy {
+    Path,
+    Modified,
+    Accessed,
+    Created,
+    Matches,
+}
+
+impl FromStr for SortBy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<SortBy, &'static str> {
+        match s {
+            "path" => Ok(SortBy::Path),
+            "modified" => Ok(SortBy::Modified),
+            "accessed" => Ok(SortBy::Accessed),
+            "created" => Ok(SortBy::Created),
+            "matches" => Ok(SortBy::Matches),
+            _ => Err("sort must be one of path, modified, accessed, created or matches"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sort {
+    pub by: SortBy,
+    pub reverse: bool,
+}
+
+impl Sort {
+    // Sorting by path or a timestamp only needs the list of files, so the
+    // files can be sorted up front and their results still stream out.
+    // Sorting by match count has to search every file first.
+    pub fn needs_results(&self) -> bool {
+        self.by == SortBy::Matches
+    }
+
+    // Sorts the files by path or by one of their timestamps. Files are
+    // compared by path when the timestamps are equal, so the output is the
+    // same every time. A timestamp the platform can't give us sorts first.
+    pub fn sort_paths(&self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        let mut keyed: Vec<(Option<SystemTime>, PathBuf)> = paths
+            .into_iter()
+            .map(|path| (self.timestamp(&path), path))
+            .collect();
+
+        keyed.sort_by(|a, b| self.order(a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1))));
+        keyed.into_iter().map(|(_, path)| path).collect()
+    }
+
+    // Sorts buffered search results by how many lines matched
+    pub fn sort_results(&self, results: &mut [BufferedResults]) {
+        results.sort_by(|a, b| self.order(a.matches.cmp(&b.matches).then_with(|| a.path.cmp(&b.path))));
+    }
+
+    fn timestamp(&self, path: &Path) -> Option<SystemTime> {
+        let metadata = fs::metadata(path).ok()?;
+        match self.by {
+            SortBy::Modified => metadata.modified().ok(),
+            SortBy::Accessed => metadata.accessed().ok(),
+            SortBy::Created => metadata.created().ok(),
+            SortBy::Path | SortBy::Matches => None,
+        }
+    }
+
+    fn order(&self, ordering: Ordering) -> Ordering {
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+enum Event {
+    Matched(usize, String, Vec<Range<usize>>),
+    Context(usize, String),
+    ContextBreak,
+}
+
+// A Sink that keeps everything found in one file so it can be replayed
+// into the real Sink later, once all the files have been put in order
+pub struct BufferedResults {
+    pub path: PathBuf,
+    pub matches: usize,
+    events: Vec<Event>,
+}
+
+impl BufferedResults {
+    pub fn new(path: &Path) -> BufferedResults {
+        BufferedResults {
+            path: path.to_path_buf(),
+            matches: 0,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn replay<S: Sink>(self, mut sink: S) -> io::Result<()> {
+        sink.begin(&self.path)?;
+        for event in self.events {
+            match event {
+                Event::Matched(number, line, matches) => sink.matched(number, &line, &matches)?,
+                Event::Context(number, line) => sink.context(number, &line)?,
+                Event::ContextBreak => sink.context_break()?,
+            }
+        }
+        sink.finish()
+    }
+}
+
+impl Sink for BufferedResults {
+    fn matched(&mut self, line_number: usize, line: &str, matches: &[Range<usize>]) -> io::Result<()> {
+        self.matches += 1;
+        self.events.push(Event::Matched(line_number, line.to_string(), matches.to_vec()));
+        Ok(())
+    }
+
+    fn context(&mut self, line_number: usize, line: &str) -> io::Result<()> {
+        self.events.push(Event::Context(line_number, line.to_string()));
+        Ok(())
+    }
+
+    fn context_break(&mut self) -> io::Result<()> {
+        self.events.push(Event::ContextBreak);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+// Single-match lines are written as &[start..end], which is what we mean
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+    use crate::sink::StandardSink;
+    use std::time::Duration;
+
+    #[test]
+    fn parse_sort_by() {
+        assert_eq!(Ok(SortBy::Path), "path".parse());
+        assert_eq!(Ok(SortBy::Matches), "matches".parse());
+        assert!("size".parse::<SortBy>().is_err());
+    }
+
+    #[test]
+    fn sort_by_path() {
+        let paths = vec![PathBuf::from("b"), PathBuf::from("c"), PathBuf::from("a")];
+
+        let sort = Sort { by: SortBy::Path, reverse: false };
+        assert_eq!(vec![PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")], sort.sort_paths(paths.clone()));
+
+        let sort = Sort { by: SortBy::Path, reverse: true };
+        assert_eq!(vec![PathBuf::from("c"), PathBuf::from("b"), PathBuf::from("a")], sort.sort_paths(paths));
+    }
+
+    #[test]
+    fn sort_by_modified() {
+        let dir = std::env::temp_dir().join(format!("minigrep-sort-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // Written in name order, but given modification times in reverse
+        let now = SystemTime::now();
+        let mut paths = Vec::new();
+        for (i, name) in ["a.txt", "b.txt", "c.txt"].iter().enumerate() {
+            let path = dir.join(name);
+            let file = fs::File::create(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(60 * (i as u64 + 1))).unwrap();
+            paths.push(path);
+        }
+
+        let sort = Sort { by: SortBy::Modified, reverse: false };
+        let sorted = sort.sort_paths(paths.clone());
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(vec![paths[2].clone(), paths[1].clone(), paths[0].clone()], sorted);
+    }
+
+    #[test]
+    fn buffered_results_sort_and_replay() {
+        let mut one = BufferedResults::new(Path::new("one.txt"));
+        one.matched(1, "x", &[0..1]).unwrap();
+        let mut two = BufferedResults::new(Path::new("two.txt"));
+        two.matched(1, "x", &[0..1]).unwrap();
+        two.context(2, "y").unwrap();
+        two.matched(3, "x", &[0..1]).unwrap();
+        let mut results = vec![two, one];
+
+        let sort = Sort { by: SortBy::Matches, reverse: false };
+        sort.sort_results(&mut results);
+
+        let mut sink = StandardSink::new(Vec::new());
+        for result in results {
+            result.replay(&mut sink).unwrap();
+        }
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!("one.txt:x\ntwo.txt:x\ntwo.txt-y\ntwo.txt:x\n", output);
+    }
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Calls `visit` for every regular file under `dir`, going into
+// subdirectories as it finds them. The order is whatever the operating
+// system returns, so use --sort when the order matters.
+//
+// Symbolic links are skipped so a link pointing back up the tree can't
+// send us around in circles.
+pub fn visit_files<F>(dir: &Path, visit: &mut F) -> io::Result<()>
+where
+    F: FnMut(&Path) -> io::Result<()>,
+{
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+
+        if file_type.is_dir() {
+            visit_files(&path, visit)?;
+        } else if file_type.is_file() {
+            visit(&path)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn finds_nested_files() {
+        let dir = std::env::temp_dir().join(format!("minigrep-walk-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested/deeper")).unwrap();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        fs::write(dir.join("nested/b.txt"), "b").unwrap();
+        fs::write(dir.join("nested/deeper/c.txt"), "c").unwrap();
+
+        let mut found: Vec<PathBuf> = Vec::new();
+        visit_files(&dir, &mut |path| {
+            found.push(path.strip_prefix(&dir).unwrap().to_path_buf());
+            Ok(())
+        }).unwrap();
+        found.sort();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            vec![PathBuf::from("a.txt"), PathBuf::from("nested/b.txt"), PathBuf::from("nested/deeper/c.txt")],
+            found
+        );
+    }
+}
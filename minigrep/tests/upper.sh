@@ -0,0 +1,3 @@
+#!/bin/sh
+# Test preprocessor for `minigrep --pre`: prints the file in upper case
+tr 'a-z' 'A-Z' < "$1"
@@ -0,0 +1,236 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, Stdout, Write};
+use std::ops::{Bound, RangeBounds};
+use std::str::FromStr;
+
+// Asking the user for a value until they give a valid one. Every exercise
+// that reads from the terminal used to have its own read_line/parse/continue
+// loop, which never said what was wrong with the input and spun forever once
+// stdin was closed. A Prompt reads from any BufRead and writes to any Write,
+// so it works just as well on a string in a test as on the terminal.
+
+// Why a prompt gave up without a value
+#[derive(Debug)]
+pub enum PromptError {
+    // The input ran out before a valid value came
+    Eof,
+    // Every one of this many attempts was invalid
+    TooManyAttempts(usize),
+    Io(io::Error),
+}
+
+impl Display for PromptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PromptError::Eof => write!(f, "the input ended before a valid value was given"),
+            PromptError::TooManyAttempts(n) => write!(f, "no valid value after {} attempts", n),
+            PromptError::Io(e) => write!(f, "couldn't read the input: {}", e),
+        }
+    }
+}
+
+impl Error for PromptError {}
+
+impl From<io::Error> for PromptError {
+    fn from(e: io::Error) -> PromptError {
+        PromptError::Io(e)
+    }
+}
+
+pub struct Prompt<R, W> {
+    input: R,
+    output: W,
+    // None keeps asking for as long as there's input
+    attempts: Option<usize>,
+}
+
+impl Prompt<io::StdinLock<'static>, Stdout> {
+    // A prompt on the terminal. It holds on to stdin until it's dropped, so
+    // drop it before reading stdin any other way.
+    pub fn stdio() -> Prompt<io::StdinLock<'static>, Stdout> {
+        Prompt::new(io::stdin().lock(), io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> Prompt<R, W> {
+    pub fn new(input: R, output: W) -> Prompt<R, W> {
+        Prompt { input, output, attempts: None }
+    }
+
+    // Give up after this many invalid answers to one question
+    pub fn attempts(mut self, attempts: usize) -> Prompt<R, W> {
+        self.attempts = Some(attempts);
+        self
+    }
+
+    // Writes `message` and reads one line, without the line ending
+    pub fn line(&mut self, message: &str) -> Result<String, PromptError> {
+        writeln!(self.output, "{}", message)?;
+        self.output.flush()?;
+
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(PromptError::Eof);
+        }
+        Ok(line.trim_end_matches(['\n', '\r']).to_string())
+    }
+
+    // Asks until the answer parses as a T
+    pub fn ask<T>(&mut self, message: &str) -> Result<T, PromptError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.ask_with(message, |_: &T| Ok(()))
+    }
+
+    // Asks until the answer parses as a T within `range`, like 1..=100 or 0..
+    pub fn ask_in_range<T, B>(&mut self, message: &str, range: B) -> Result<T, PromptError>
+    where
+        T: FromStr + PartialOrd + Display,
+        T::Err: Display,
+        B: RangeBounds<T>,
+    {
+        self.ask_with(message, |value: &T| {
+            if range.contains(value) {
+                Ok(())
+            } else {
+                Err(describe(&range))
+            }
+        })
+    }
+
+    // Asks until the answer parses as a T that `validate` accepts. When it
+    // doesn't, the message `validate` gives is shown before asking again.
+    pub fn ask_with<T, F>(&mut self, message: &str, validate: F) -> Result<T, PromptError>
+    where
+        T: FromStr,
+        T::Err: Display,
+        F: Fn(&T) -> Result<(), String>,
+    {
+        let mut tries = 0;
+        loop {
+            if let Some(attempts) = self.attempts {
+                if tries == attempts {
+                    return Err(PromptError::TooManyAttempts(attempts));
+                }
+            }
+            tries += 1;
+
+            let line = self.line(message)?;
+            let problem = match line.trim().parse::<T>() {
+                Ok(value) => match validate(&value) {
+                    Ok(()) => return Ok(value),
+                    Err(problem) => problem,
+                },
+                Err(e) => format!("'{}' isn't valid: {}", line.trim(), e),
+            };
+            writeln!(self.output, "{}", problem)?;
+        }
+    }
+}
+
+// What a value has to be to fall in the range, for the error message
+fn describe<T: Display, B: RangeBounds<T>>(range: &B) -> String {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(lower), Bound::Included(upper)) => format!("It has to be from {} to {}", lower, upper),
+        (Bound::Included(lower), Bound::Excluded(upper)) => format!("It has to be from {} to below {}", lower, upper),
+        (Bound::Included(lower), Bound::Unbounded) => format!("It has to be at least {}", lower),
+        (Bound::Excluded(lower), Bound::Unbounded) => format!("It has to be more than {}", lower),
+        (Bound::Unbounded, Bound::Included(upper)) => format!("It has to be at most {}", upper),
+        (Bound::Unbounded, Bound::Excluded(upper)) => format!("It has to be less than {}", upper),
+        (Bound::Excluded(lower), Bound::Included(upper)) => format!("It has to be more than {} and at most {}", lower, upper),
+        (Bound::Excluded(lower), Bound::Excluded(upper)) => format!("It has to be more than {} and less than {}", lower, upper),
+        (Bound::Unbounded, Bound::Unbounded) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs `ask` on a prompt reading `input`, returning its answer and
+    // everything it wrote
+    fn answer<T, F>(input: &str, attempts: Option<usize>, ask: F) -> (Result<T, PromptError>, String)
+    where
+        F: FnOnce(&mut Prompt<&[u8], &mut Vec<u8>>) -> Result<T, PromptError>,
+    {
+        let mut output = Vec::new();
+        let result = {
+            let mut prompt = Prompt::new(input.as_bytes(), &mut output);
+            if let Some(attempts) = attempts {
+                prompt = prompt.attempts(attempts);
+            }
+            ask(&mut prompt)
+        };
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn parses_any_type() {
+        let (result, output) = answer("  42 \n", None, |p| p.ask::<i32>("Number:"));
+        assert_eq!(42, result.unwrap());
+        assert_eq!("Number:\n", output);
+
+        let (result, _) = answer("2.5\r\n", None, |p| p.ask::<f64>("Number:"));
+        assert_eq!(2.5, result.unwrap());
+
+        let (result, _) = answer("true\n", None, |p| p.ask::<bool>("Yes or no:"));
+        assert!(result.unwrap());
+    }
+
+    #[test]
+    fn explains_bad_input() {
+        let (result, output) = answer("abc\n7\n", None, |p| p.ask::<u32>("Guess:"));
+        assert_eq!(7, result.unwrap());
+        assert_eq!("Guess:\n'abc' isn't valid: invalid digit found in string\nGuess:\n", output);
+    }
+
+    #[test]
+    fn ranges() {
+        let (result, output) = answer("0\n101\n100\n", None, |p| p.ask_in_range("Guess:", 1..=100));
+        assert_eq!(100, result.unwrap());
+        assert_eq!(2, output.matches("It has to be from 1 to 100\n").count());
+
+        let (result, output) = answer("-1\n0\n", None, |p| p.ask_in_range("Count:", 0..));
+        assert_eq!(0, result.unwrap());
+        assert!(output.contains("It has to be at least 0\n"));
+
+        assert_eq!("It has to be less than 12", describe(&(..12)));
+    }
+
+    #[test]
+    fn validators() {
+        let even = |n: &i32| if n % 2 == 0 { Ok(()) } else { Err(format!("{} is odd", n)) };
+        let (result, output) = answer("3\n4\n", None, |p| p.ask_with("Even number:", even));
+        assert_eq!(4, result.unwrap());
+        assert!(output.contains("3 is odd\n"));
+    }
+
+    #[test]
+    fn gives_up() {
+        let (result, output) = answer("x\ny\nz\n1\n", Some(3), |p| p.ask::<i32>("Number:"));
+        match result {
+            Err(PromptError::TooManyAttempts(3)) => (),
+            other => panic!("expected to give up after 3 attempts, got {:?}", other),
+        }
+        assert_eq!(3, output.matches("Number:\n").count());
+
+        let (result, _) = answer("x\n", None, |p| p.ask::<i32>("Number:"));
+        match result {
+            Err(PromptError::Eof) => (),
+            other => panic!("expected the end of the input, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn raw_lines() {
+        let (result, _) = answer("Add Sally to Sales\r\nnext", None, |p| {
+            let first = p.line("Command:")?;
+            let second = p.line("Command:")?;
+            Ok((first, second))
+        });
+        assert_eq!((String::from("Add Sally to Sales"), String::from("next")), result.unwrap());
+    }
+}